@@ -0,0 +1,199 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::Signer,
+    pubkey::Pubkey,
+    sysvars::{rent::Rent, Sysvar},
+    ProgramResult,
+};
+
+use crate::instructions::{Allocate, Assign, CreateAccount, Transfer};
+
+/// Create a new rent-exempt account, even if its address already holds lamports.
+///
+/// The System program `CreateAccount` instruction fails when the new account
+/// already has lamports, which allows anyone to prevent the creation of an
+/// account at a known address (e.g., a program derived address) by transferring
+/// lamports to it. When the account already has lamports, this helper instead
+/// transfers only the lamports missing to make it rent-exempt, and then
+/// allocates and assigns the account.
+///
+/// The System program `Allocate` instruction fails with `AccountAlreadyInUse`
+/// when the account already has data or is not owned by the System program, so
+/// the helper fails in the same way for an account that is already allocated or
+/// assigned to a program, regardless of its lamports.
+///
+/// The rent-exempt minimum balance is computed using the `Rent` sysvar. The
+/// `signers` used when invoking the helper are used for all instructions, so
+/// they should include the seeds of the new account when it is a program
+/// derived address.
+///
+/// ### Accounts:
+///   0. `[WRITE, SIGNER]` Funding account
+///   1. `[WRITE, SIGNER]` New account
+pub struct CreateAccountIfNeeded<'a> {
+    /// Funding account.
+    pub from: &'a AccountInfo,
+
+    /// New account.
+    pub to: &'a AccountInfo,
+
+    /// Number of bytes of memory to allocate.
+    pub space: u64,
+
+    /// Address of program that will own the new account.
+    pub owner: &'a Pubkey,
+}
+
+impl CreateAccountIfNeeded<'_> {
+    #[inline(always)]
+    pub fn invoke(&self) -> ProgramResult {
+        self.invoke_signed(&[])
+    }
+
+    pub fn invoke_signed(&self, signers: &[Signer]) -> ProgramResult {
+        let minimum_balance = Rent::get()?.minimum_balance(self.space as usize);
+        let lamports = self.to.lamports();
+
+        if lamports == 0 {
+            return CreateAccount {
+                from: self.from,
+                to: self.to,
+                lamports: minimum_balance,
+                space: self.space,
+                owner: self.owner,
+            }
+            .invoke_signed(signers);
+        }
+
+        if lamports < minimum_balance {
+            Transfer {
+                from: self.from,
+                to: self.to,
+                lamports: minimum_balance - lamports,
+            }
+            .invoke_signed(signers)?;
+        }
+
+        Allocate {
+            account: self.to,
+            space: self.space,
+        }
+        .invoke_signed(signers)?;
+
+        Assign {
+            account: self.to,
+            owner: self.owner,
+        }
+        .invoke_signed(signers)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::tests::{process, CALLER};
+    use pinocchio::{
+        instruction::AccountMeta,
+        program_error::ProgramError,
+        testing::{default_rent, AccountBuilder, InstructionError, TestAccount},
+    };
+
+    const PAYER: Pubkey = [1; 32];
+    const ACCOUNT: Pubkey = [2; 32];
+
+    const SPACE: u64 = 100;
+
+    /// Creates the account using the `CreateAccountIfNeeded` helper.
+    fn caller(program_id: &Pubkey, accounts: &[AccountInfo], _: &[u8]) -> ProgramResult {
+        let [from, to, ..] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        CreateAccountIfNeeded {
+            from,
+            to,
+            space: SPACE,
+            owner: program_id,
+        }
+        .invoke()
+    }
+
+    fn create(accounts: &mut [TestAccount]) -> Result<(), InstructionError> {
+        let metas = [
+            AccountMeta::writable_signer(&PAYER),
+            AccountMeta::writable_signer(&ACCOUNT),
+            AccountMeta::readonly(&crate::ID),
+        ];
+        process(caller, &metas, &[], accounts)
+    }
+
+    fn with_account(account: TestAccount) -> [TestAccount; 3] {
+        [
+            AccountBuilder::new(PAYER).lamports(1_000_000_000).build(),
+            account,
+            AccountBuilder::new(crate::ID).executable(true).build(),
+        ]
+    }
+
+    fn minimum_balance() -> u64 {
+        default_rent().minimum_balance(SPACE as usize)
+    }
+
+    #[test]
+    fn test_create_account() {
+        let mut accounts = with_account(AccountBuilder::new(ACCOUNT).build());
+        create(&mut accounts).unwrap();
+
+        assert_eq!(accounts[1].owner(), &CALLER);
+        assert_eq!(accounts[1].data_len(), SPACE as usize);
+        assert_eq!(accounts[1].lamports(), minimum_balance());
+        assert_eq!(accounts[0].lamports(), 1_000_000_000 - minimum_balance());
+    }
+
+    #[test]
+    fn test_create_underfunded_account() {
+        let mut accounts = with_account(AccountBuilder::new(ACCOUNT).lamports(1_000).build());
+        create(&mut accounts).unwrap();
+
+        assert_eq!(accounts[1].owner(), &CALLER);
+        assert_eq!(accounts[1].data_len(), SPACE as usize);
+        assert_eq!(accounts[1].lamports(), minimum_balance());
+        assert_eq!(
+            accounts[0].lamports(),
+            1_000_000_000 - (minimum_balance() - 1_000)
+        );
+    }
+
+    #[test]
+    fn test_create_funded_account() {
+        let lamports = minimum_balance() + 1_000;
+        let mut accounts = with_account(AccountBuilder::new(ACCOUNT).lamports(lamports).build());
+        create(&mut accounts).unwrap();
+
+        assert_eq!(accounts[1].owner(), &CALLER);
+        assert_eq!(accounts[1].data_len(), SPACE as usize);
+        assert_eq!(accounts[1].lamports(), lamports);
+        assert_eq!(accounts[0].lamports(), 1_000_000_000);
+    }
+
+    #[test]
+    fn test_create_allocated_account() {
+        // AccountAlreadyInUse
+        let error = Err(InstructionError::Program(ProgramError::Custom(0)));
+
+        let mut accounts = with_account(
+            AccountBuilder::new(ACCOUNT)
+                .lamports(minimum_balance())
+                .space(10)
+                .build(),
+        );
+        assert_eq!(create(&mut accounts), error);
+
+        let mut accounts = with_account(
+            AccountBuilder::new(ACCOUNT)
+                .owner(CALLER)
+                .lamports(minimum_balance())
+                .build(),
+        );
+        assert_eq!(create(&mut accounts), error);
+    }
+}
@@ -0,0 +1,35 @@
+mod create_account_if_needed;
+mod resize;
+
+pub use create_account_if_needed::*;
+pub use resize::*;
+
+#[cfg(test)]
+mod tests {
+    use pinocchio::{
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+        testing::{Executor, InstructionError, ProcessInstruction, TestAccount},
+    };
+
+    /// Program that invokes the helpers.
+    pub(crate) const CALLER: Pubkey = [0xca; 32];
+
+    /// Processes an instruction of the `CALLER` program, which runs `processor`.
+    pub(crate) fn process(
+        processor: ProcessInstruction,
+        metas: &[AccountMeta],
+        data: &[u8],
+        accounts: &mut [TestAccount],
+    ) -> Result<(), InstructionError> {
+        let mut executor = Executor::new();
+        executor.add_processor(CALLER, processor);
+
+        let instruction = Instruction {
+            program_id: &CALLER,
+            accounts: metas,
+            data,
+        };
+        executor.process_instruction(&instruction, accounts)
+    }
+}
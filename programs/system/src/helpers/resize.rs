@@ -0,0 +1,244 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::Signer,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvars::{rent::Rent, Sysvar},
+    ProgramResult,
+};
+
+use crate::instructions::Transfer;
+
+/// Resize the data of an account, keeping it rent-exempt.
+///
+/// When the account grows, the lamports required to keep it rent-exempt are
+/// transferred from the payer account through the System program. When the
+/// account shrinks, the difference between the rent-exempt minimum balance of
+/// the previous and the new length is moved to the refund account; any other
+/// lamports held by the account are left untouched.
+///
+/// Only the program that owns the account can resize it and debit its lamports,
+/// so the helper fails with `InvalidAccountOwner` when the account is not owned
+/// by `program_id`.
+///
+/// The account is resized before any lamports are moved, so a resize beyond
+/// the [`MAX_PERMITTED_DATA_INCREASE`] from the original length of the account
+/// fails without performing the transfer, even after several resizes in the
+/// same instruction.
+///
+/// ### Accounts:
+///   0. `[WRITE]` Account to resize (owned by the executing program)
+///   1. `[WRITE, SIGNER]` Payer account
+///   2. `[WRITE]` Refund account
+///
+/// [`MAX_PERMITTED_DATA_INCREASE`]: pinocchio::account_info::MAX_PERMITTED_DATA_INCREASE
+pub struct Resize<'a> {
+    /// Account to resize.
+    pub account: &'a AccountInfo,
+
+    /// Account that pays for the rent when the account grows.
+    pub payer: &'a AccountInfo,
+
+    /// Account that receives the excess lamports when the account shrinks.
+    pub refund: &'a AccountInfo,
+
+    /// Id of the executing program, which must own the account.
+    pub program_id: &'a Pubkey,
+
+    /// New length of the account data.
+    pub new_len: usize,
+
+    /// Indicates whether the new space should be zero-initialized.
+    pub zero_init: bool,
+}
+
+impl Resize<'_> {
+    #[inline(always)]
+    pub fn invoke(&self) -> ProgramResult {
+        self.invoke_signed(&[])
+    }
+
+    pub fn invoke_signed(&self, signers: &[Signer]) -> ProgramResult {
+        if self.account.owner() != self.program_id {
+            return Err(ProgramError::InvalidAccountOwner);
+        }
+
+        let previous_len = self.account.data_len();
+        self.account.realloc(self.new_len, self.zero_init)?;
+
+        let rent = Rent::get()?;
+        let minimum_balance = rent.minimum_balance(self.new_len);
+        let lamports = self.account.lamports();
+
+        if lamports < minimum_balance {
+            Transfer {
+                from: self.payer,
+                to: self.account,
+                lamports: minimum_balance - lamports,
+            }
+            .invoke_signed(signers)?;
+        } else if self.new_len < previous_len {
+            let refund = (rent.minimum_balance(previous_len) - minimum_balance)
+                .min(lamports - minimum_balance);
+
+            if refund > 0 {
+                self.account
+                    .transfer_lamports(self.refund, refund, self.program_id, None)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::tests::{process, CALLER};
+    use pinocchio::{
+        account_info::MAX_PERMITTED_DATA_INCREASE,
+        instruction::AccountMeta,
+        testing::{default_rent, AccountBuilder, InstructionError, TestAccount},
+    };
+
+    const ACCOUNT: Pubkey = [1; 32];
+    const PAYER: Pubkey = [2; 32];
+    const REFUND: Pubkey = [3; 32];
+
+    /// Resizes the account to each length in the instruction data, in order.
+    fn caller(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+        let [account, payer, refund, ..] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        for new_len in data.chunks_exact(8) {
+            Resize {
+                account,
+                payer,
+                refund,
+                program_id,
+                new_len: u64::from_le_bytes(new_len.try_into().unwrap()) as usize,
+                zero_init: true,
+            }
+            .invoke()?;
+        }
+
+        Ok(())
+    }
+
+    fn resize(accounts: &mut [TestAccount], lengths: &[u64]) -> Result<(), InstructionError> {
+        let mut data = [0; 32];
+        for (chunk, len) in data.chunks_exact_mut(8).zip(lengths) {
+            chunk.copy_from_slice(&len.to_le_bytes());
+        }
+        let metas = [
+            AccountMeta::writable(&ACCOUNT),
+            AccountMeta::writable_signer(&PAYER),
+            AccountMeta::writable(&REFUND),
+            AccountMeta::readonly(&crate::ID),
+        ];
+        process(caller, &metas, &data[..lengths.len() * 8], accounts)
+    }
+
+    fn accounts(len: usize, lamports: u64) -> [TestAccount; 4] {
+        [
+            AccountBuilder::new(ACCOUNT)
+                .owner(CALLER)
+                .space(len)
+                .lamports(lamports)
+                .build(),
+            AccountBuilder::new(PAYER).lamports(1_000_000_000).build(),
+            AccountBuilder::new(REFUND).build(),
+            AccountBuilder::new(crate::ID).executable(true).build(),
+        ]
+    }
+
+    fn minimum_balance(len: usize) -> u64 {
+        default_rent().minimum_balance(len)
+    }
+
+    #[test]
+    fn test_grow() {
+        let mut accounts = accounts(10, minimum_balance(10) + 50);
+        resize(&mut accounts, &[100]).unwrap();
+
+        assert_eq!(accounts[0].data_len(), 100);
+        assert_eq!(accounts[0].lamports(), minimum_balance(100));
+        assert_eq!(
+            accounts[1].lamports(),
+            1_000_000_000 - (minimum_balance(100) - minimum_balance(10) - 50)
+        );
+        assert_eq!(accounts[2].lamports(), 0);
+    }
+
+    #[test]
+    fn test_shrink() {
+        // only the rent released by the shrink is refunded
+        let mut accounts = accounts(100, minimum_balance(100) + 50);
+        resize(&mut accounts, &[10]).unwrap();
+
+        assert_eq!(accounts[0].data_len(), 10);
+        assert_eq!(accounts[0].lamports(), minimum_balance(10) + 50);
+        assert_eq!(accounts[1].lamports(), 1_000_000_000);
+        assert_eq!(
+            accounts[2].lamports(),
+            minimum_balance(100) - minimum_balance(10)
+        );
+
+        // nothing is refunded when the length does not shrink
+        resize(&mut accounts, &[10]).unwrap();
+
+        assert_eq!(accounts[0].lamports(), minimum_balance(10) + 50);
+        assert_eq!(
+            accounts[2].lamports(),
+            minimum_balance(100) - minimum_balance(10)
+        );
+    }
+
+    #[test]
+    fn test_grow_past_limit() {
+        let mut accounts = accounts(0, minimum_balance(0));
+
+        // the limit applies to the original length, across several resizes
+        assert_eq!(
+            resize(
+                &mut accounts,
+                &[
+                    MAX_PERMITTED_DATA_INCREASE as u64 / 2,
+                    MAX_PERMITTED_DATA_INCREASE as u64,
+                    MAX_PERMITTED_DATA_INCREASE as u64 + 1,
+                ]
+            ),
+            Err(InstructionError::Program(ProgramError::InvalidRealloc))
+        );
+        assert_eq!(accounts[0].data_len(), 0);
+        assert_eq!(accounts[1].lamports(), 1_000_000_000);
+
+        resize(&mut accounts, &[MAX_PERMITTED_DATA_INCREASE as u64]).unwrap();
+
+        assert_eq!(accounts[0].data_len(), MAX_PERMITTED_DATA_INCREASE);
+        assert_eq!(
+            accounts[0].lamports(),
+            minimum_balance(MAX_PERMITTED_DATA_INCREASE)
+        );
+    }
+
+    #[test]
+    fn test_not_owned() {
+        let mut accounts = accounts(100, minimum_balance(100) + 50);
+        accounts[0] = AccountBuilder::new(ACCOUNT)
+            .owner([5; 32])
+            .space(100)
+            .lamports(minimum_balance(100) + 50)
+            .build();
+
+        for len in [10, 200] {
+            assert_eq!(
+                resize(&mut accounts, &[len]),
+                Err(InstructionError::Program(ProgramError::InvalidAccountOwner))
+            );
+            assert_eq!(accounts[0].data_len(), 100);
+            assert_eq!(accounts[0].lamports(), minimum_balance(100) + 50);
+        }
+    }
+}
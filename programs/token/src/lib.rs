@@ -2,6 +2,8 @@
 
 pub mod instructions;
 pub mod state;
+#[cfg(any(feature = "test-utils", test))]
+pub mod testing;
 
 pinocchio_pubkey::declare_id!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
 
@@ -0,0 +1,1093 @@
+//! Emulation of the Token program for native (host) tests.
+//!
+//! The [`process_instruction`] function implements the instructions wrapped by this
+//! crate, so it can be registered with a `pinocchio::testing::Executor`:
+//!
+//! ```ignore
+//! executor.add_processor(pinocchio_token::ID, pinocchio_token::testing::process_instruction);
+//! ```
+//!
+//! Multisig authorities are not supported &mdash; only single signer authorities are
+//! accepted.
+
+use pinocchio::{
+    account_info::{AccountInfo, Ref, RefMut},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvars::{rent::Rent, Sysvar},
+    ProgramResult,
+};
+
+use crate::{
+    state::{AccountState, Mint, TokenAccount},
+    ID,
+};
+
+/// Address of the native mint.
+pub const NATIVE_MINT: Pubkey =
+    pinocchio_pubkey::pubkey!("So11111111111111111111111111111111111111112");
+
+/// Errors of the Token program, reported as custom program errors.
+#[repr(u32)]
+#[derive(Clone, Copy)]
+enum TokenError {
+    NotRentExempt = 0,
+    InsufficientFunds = 1,
+    InvalidMint = 2,
+    MintMismatch = 3,
+    OwnerMismatch = 4,
+    FixedSupply = 5,
+    AlreadyInUse = 6,
+    NativeNotSupported = 10,
+    NonNativeHasBalance = 11,
+    InvalidInstruction = 12,
+    InvalidState = 13,
+    Overflow = 14,
+    AuthorityTypeNotSupported = 15,
+    MintCannotFreeze = 16,
+    AccountFrozen = 17,
+    MintDecimalsMismatch = 18,
+    NonNativeNotSupported = 19,
+}
+
+impl From<TokenError> for ProgramError {
+    fn from(error: TokenError) -> Self {
+        ProgramError::Custom(error as u32)
+    }
+}
+
+// Offsets of the mint fields.
+const MINT_AUTHORITY: usize = 0;
+const MINT_SUPPLY: usize = 36;
+const MINT_DECIMALS: usize = 44;
+const MINT_IS_INITIALIZED: usize = 45;
+const MINT_FREEZE_AUTHORITY: usize = 46;
+
+// Offsets of the token account fields.
+const ACCOUNT_MINT: usize = 0;
+const ACCOUNT_OWNER: usize = 32;
+const ACCOUNT_AMOUNT: usize = 64;
+const ACCOUNT_DELEGATE: usize = 72;
+const ACCOUNT_STATE: usize = 108;
+const ACCOUNT_IS_NATIVE: usize = 109;
+const ACCOUNT_DELEGATED_AMOUNT: usize = 121;
+const ACCOUNT_CLOSE_AUTHORITY: usize = 129;
+
+/// Processes a Token program instruction.
+///
+/// The behaviour follows the SPL Token program, including the errors returned.
+pub fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    if program_id != &ID {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let (discriminator, data) = instruction_data
+        .split_first()
+        .ok_or(TokenError::InvalidInstruction)?;
+
+    match discriminator {
+        // InitializeMint
+        0 => {
+            let [mint, _rent_sysvar, ..] = accounts else {
+                return Err(ProgramError::NotEnoughAccountKeys);
+            };
+            initialize_mint(mint, data)
+        }
+        // InitializeAccount
+        1 => {
+            let [account, mint, owner, _rent_sysvar, ..] = accounts else {
+                return Err(ProgramError::NotEnoughAccountKeys);
+            };
+            initialize_account(account, mint, owner.key())
+        }
+        // Transfer
+        3 => {
+            let [source, destination, authority, ..] = accounts else {
+                return Err(ProgramError::NotEnoughAccountKeys);
+            };
+            transfer(
+                source,
+                None,
+                destination,
+                authority,
+                read_u64(data, 0)?,
+                None,
+            )
+        }
+        // Approve
+        4 => {
+            let [source, delegate, owner, ..] = accounts else {
+                return Err(ProgramError::NotEnoughAccountKeys);
+            };
+            approve(source, None, delegate, owner, read_u64(data, 0)?, None)
+        }
+        // Revoke
+        5 => {
+            let [source, owner, ..] = accounts else {
+                return Err(ProgramError::NotEnoughAccountKeys);
+            };
+            let mut source_data = token_account_mut(source)?;
+            validate_owner(pubkey_at(&source_data, ACCOUNT_OWNER), owner)?;
+            set_option(&mut source_data, ACCOUNT_DELEGATE, None);
+            write_u64(&mut source_data, ACCOUNT_DELEGATED_AMOUNT, 0);
+            Ok(())
+        }
+        // SetAuthority
+        6 => {
+            let [account, authority, ..] = accounts else {
+                return Err(ProgramError::NotEnoughAccountKeys);
+            };
+            let authority_type = *data.first().ok_or(TokenError::InvalidInstruction)?;
+            let new_authority = read_option(data, 1)?;
+            set_authority(account, authority, authority_type, new_authority)
+        }
+        // MintTo
+        7 => {
+            let [mint, destination, authority, ..] = accounts else {
+                return Err(ProgramError::NotEnoughAccountKeys);
+            };
+            mint_to(mint, destination, authority, read_u64(data, 0)?, None)
+        }
+        // Burn
+        8 => {
+            let [account, mint, authority, ..] = accounts else {
+                return Err(ProgramError::NotEnoughAccountKeys);
+            };
+            burn(account, mint, authority, read_u64(data, 0)?, None)
+        }
+        // CloseAccount
+        9 => {
+            let [account, destination, authority, ..] = accounts else {
+                return Err(ProgramError::NotEnoughAccountKeys);
+            };
+            close_account(account, destination, authority)
+        }
+        // FreezeAccount
+        10 => {
+            let [account, mint, authority, ..] = accounts else {
+                return Err(ProgramError::NotEnoughAccountKeys);
+            };
+            toggle_freeze(account, mint, authority, true)
+        }
+        // ThawAccount
+        11 => {
+            let [account, mint, authority, ..] = accounts else {
+                return Err(ProgramError::NotEnoughAccountKeys);
+            };
+            toggle_freeze(account, mint, authority, false)
+        }
+        // TransferChecked
+        12 => {
+            let [source, mint, destination, authority, ..] = accounts else {
+                return Err(ProgramError::NotEnoughAccountKeys);
+            };
+            let decimals = read_u8(data, 8)?;
+            transfer(
+                source,
+                Some(mint),
+                destination,
+                authority,
+                read_u64(data, 0)?,
+                Some(decimals),
+            )
+        }
+        // ApproveChecked
+        13 => {
+            let [source, mint, delegate, owner, ..] = accounts else {
+                return Err(ProgramError::NotEnoughAccountKeys);
+            };
+            let decimals = read_u8(data, 8)?;
+            approve(
+                source,
+                Some(mint),
+                delegate,
+                owner,
+                read_u64(data, 0)?,
+                Some(decimals),
+            )
+        }
+        // MintToChecked
+        14 => {
+            let [mint, destination, authority, ..] = accounts else {
+                return Err(ProgramError::NotEnoughAccountKeys);
+            };
+            let decimals = read_u8(data, 8)?;
+            mint_to(
+                mint,
+                destination,
+                authority,
+                read_u64(data, 0)?,
+                Some(decimals),
+            )
+        }
+        // BurnChecked
+        15 => {
+            let [account, mint, authority, ..] = accounts else {
+                return Err(ProgramError::NotEnoughAccountKeys);
+            };
+            let decimals = read_u8(data, 8)?;
+            burn(account, mint, authority, read_u64(data, 0)?, Some(decimals))
+        }
+        // InitializeAccount2
+        16 => {
+            let [account, mint, _rent_sysvar, ..] = accounts else {
+                return Err(ProgramError::NotEnoughAccountKeys);
+            };
+            initialize_account(account, mint, &read_pubkey(data, 0)?)
+        }
+        // SyncNative
+        17 => {
+            let [account, ..] = accounts else {
+                return Err(ProgramError::NotEnoughAccountKeys);
+            };
+            let mut account_data = token_account_mut(account)?;
+            let reserve = native_reserve(&account_data).ok_or(TokenError::NonNativeNotSupported)?;
+            let amount = account
+                .lamports()
+                .checked_sub(reserve)
+                .ok_or(TokenError::Overflow)?;
+
+            if amount < read_u64(&account_data, ACCOUNT_AMOUNT)? {
+                return Err(TokenError::InvalidState.into());
+            }
+
+            write_u64(&mut account_data, ACCOUNT_AMOUNT, amount);
+            Ok(())
+        }
+        // InitializeAccount3
+        18 => {
+            let [account, mint, ..] = accounts else {
+                return Err(ProgramError::NotEnoughAccountKeys);
+            };
+            initialize_account(account, mint, &read_pubkey(data, 0)?)
+        }
+        // InitializeMint2
+        20 => {
+            let [mint, ..] = accounts else {
+                return Err(ProgramError::NotEnoughAccountKeys);
+            };
+            initialize_mint(mint, data)
+        }
+        _ => Err(TokenError::InvalidInstruction.into()),
+    }
+}
+
+fn initialize_mint(mint: &AccountInfo, data: &[u8]) -> ProgramResult {
+    let decimals = read_u8(data, 0)?;
+    let mint_authority = read_pubkey(data, 1)?;
+    let freeze_authority = read_option(data, 33)?;
+
+    check_owner(mint)?;
+    if mint.data_len() != Mint::LEN {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let mut mint_data = mint.try_borrow_mut_data()?;
+
+    if mint_data[MINT_IS_INITIALIZED] != 0 {
+        return Err(TokenError::AlreadyInUse.into());
+    }
+    if !Rent::get()?.is_exempt(mint.lamports(), Mint::LEN) {
+        return Err(TokenError::NotRentExempt.into());
+    }
+
+    set_option(&mut mint_data, MINT_AUTHORITY, Some(&mint_authority));
+    mint_data[MINT_DECIMALS] = decimals;
+    mint_data[MINT_IS_INITIALIZED] = 1;
+    set_option(
+        &mut mint_data,
+        MINT_FREEZE_AUTHORITY,
+        freeze_authority.as_ref(),
+    );
+
+    Ok(())
+}
+
+fn initialize_account(account: &AccountInfo, mint: &AccountInfo, owner: &Pubkey) -> ProgramResult {
+    check_owner(account)?;
+    if account.data_len() != TokenAccount::LEN {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let mut account_data = account.try_borrow_mut_data()?;
+
+    if account_data[ACCOUNT_STATE] != AccountState::Uninitialized as u8 {
+        return Err(TokenError::AlreadyInUse.into());
+    }
+
+    let minimum_balance = Rent::get()?.minimum_balance(TokenAccount::LEN);
+    if account.lamports() < minimum_balance {
+        return Err(TokenError::NotRentExempt.into());
+    }
+
+    if mint.key() != &NATIVE_MINT {
+        check_owner(mint)?;
+        initialized_mint(mint).map_err(|_| TokenError::InvalidMint)?;
+    }
+
+    account_data[ACCOUNT_MINT..ACCOUNT_MINT + 32].copy_from_slice(mint.key());
+    account_data[ACCOUNT_OWNER..ACCOUNT_OWNER + 32].copy_from_slice(owner);
+    account_data[ACCOUNT_STATE] = AccountState::Initialized as u8;
+
+    if mint.key() == &NATIVE_MINT {
+        account_data[ACCOUNT_IS_NATIVE] = 1;
+        write_u64(&mut account_data, ACCOUNT_IS_NATIVE + 4, minimum_balance);
+        write_u64(
+            &mut account_data,
+            ACCOUNT_AMOUNT,
+            account.lamports() - minimum_balance,
+        );
+    }
+
+    Ok(())
+}
+
+fn transfer(
+    source: &AccountInfo,
+    mint: Option<&AccountInfo>,
+    destination: &AccountInfo,
+    authority: &AccountInfo,
+    amount: u64,
+    expected_decimals: Option<u8>,
+) -> ProgramResult {
+    let mut source_data = token_account_mut(source)?;
+
+    if source_data[ACCOUNT_STATE] == AccountState::Frozen as u8 {
+        return Err(TokenError::AccountFrozen.into());
+    }
+    if read_u64(&source_data, ACCOUNT_AMOUNT)? < amount {
+        return Err(TokenError::InsufficientFunds.into());
+    }
+
+    let source_mint = pubkey_at(&source_data, ACCOUNT_MINT);
+
+    if let (Some(mint), Some(expected_decimals)) = (mint, expected_decimals) {
+        if mint.key() != &source_mint {
+            return Err(TokenError::MintMismatch.into());
+        }
+        let mint_data = initialized_mint(mint)?;
+        if mint_data[MINT_DECIMALS] != expected_decimals {
+            return Err(TokenError::MintDecimalsMismatch.into());
+        }
+    }
+
+    validate_authority(&mut source_data, authority, amount)?;
+
+    // Self-transfers only validate the accounts.
+    if source.key() == destination.key() {
+        return Ok(());
+    }
+
+    let mut destination_data = token_account_mut(destination)?;
+
+    if destination_data[ACCOUNT_STATE] == AccountState::Frozen as u8 {
+        return Err(TokenError::AccountFrozen.into());
+    }
+    if pubkey_at(&destination_data, ACCOUNT_MINT) != source_mint {
+        return Err(TokenError::MintMismatch.into());
+    }
+
+    let source_amount = read_u64(&source_data, ACCOUNT_AMOUNT)? - amount;
+    let destination_amount = read_u64(&destination_data, ACCOUNT_AMOUNT)?
+        .checked_add(amount)
+        .ok_or(TokenError::Overflow)?;
+
+    write_u64(&mut source_data, ACCOUNT_AMOUNT, source_amount);
+    write_u64(&mut destination_data, ACCOUNT_AMOUNT, destination_amount);
+
+    if native_reserve(&source_data).is_some() {
+        let source_lamports = source
+            .lamports()
+            .checked_sub(amount)
+            .ok_or(TokenError::Overflow)?;
+        let destination_lamports = destination
+            .lamports()
+            .checked_add(amount)
+            .ok_or(TokenError::Overflow)?;
+
+        *source.try_borrow_mut_lamports()? = source_lamports;
+        *destination.try_borrow_mut_lamports()? = destination_lamports;
+    }
+
+    Ok(())
+}
+
+fn approve(
+    source: &AccountInfo,
+    mint: Option<&AccountInfo>,
+    delegate: &AccountInfo,
+    owner: &AccountInfo,
+    amount: u64,
+    expected_decimals: Option<u8>,
+) -> ProgramResult {
+    let mut source_data = token_account_mut(source)?;
+
+    if source_data[ACCOUNT_STATE] == AccountState::Frozen as u8 {
+        return Err(TokenError::AccountFrozen.into());
+    }
+
+    if let (Some(mint), Some(expected_decimals)) = (mint, expected_decimals) {
+        if mint.key() != &pubkey_at(&source_data, ACCOUNT_MINT) {
+            return Err(TokenError::MintMismatch.into());
+        }
+        let mint_data = initialized_mint(mint)?;
+        if mint_data[MINT_DECIMALS] != expected_decimals {
+            return Err(TokenError::MintDecimalsMismatch.into());
+        }
+    }
+
+    validate_owner(pubkey_at(&source_data, ACCOUNT_OWNER), owner)?;
+
+    set_option(&mut source_data, ACCOUNT_DELEGATE, Some(delegate.key()));
+    write_u64(&mut source_data, ACCOUNT_DELEGATED_AMOUNT, amount);
+
+    Ok(())
+}
+
+fn set_authority(
+    account: &AccountInfo,
+    authority: &AccountInfo,
+    authority_type: u8,
+    new_authority: Option<Pubkey>,
+) -> ProgramResult {
+    check_owner(account)?;
+
+    match account.data_len() {
+        TokenAccount::LEN => {
+            let mut account_data = token_account_mut(account)?;
+
+            if account_data[ACCOUNT_STATE] == AccountState::Frozen as u8 {
+                return Err(TokenError::AccountFrozen.into());
+            }
+
+            match authority_type {
+                // AccountOwner
+                2 => {
+                    validate_owner(pubkey_at(&account_data, ACCOUNT_OWNER), authority)?;
+                    let new_owner = new_authority.ok_or(TokenError::InvalidInstruction)?;
+
+                    account_data[ACCOUNT_OWNER..ACCOUNT_OWNER + 32].copy_from_slice(&new_owner);
+                    set_option(&mut account_data, ACCOUNT_DELEGATE, None);
+                    write_u64(&mut account_data, ACCOUNT_DELEGATED_AMOUNT, 0);
+
+                    if native_reserve(&account_data).is_some() {
+                        set_option(&mut account_data, ACCOUNT_CLOSE_AUTHORITY, None);
+                    }
+                }
+                // CloseAccount
+                3 => {
+                    let current = option_at(&account_data, ACCOUNT_CLOSE_AUTHORITY)
+                        .unwrap_or(pubkey_at(&account_data, ACCOUNT_OWNER));
+                    validate_owner(current, authority)?;
+                    set_option(
+                        &mut account_data,
+                        ACCOUNT_CLOSE_AUTHORITY,
+                        new_authority.as_ref(),
+                    );
+                }
+                _ => return Err(TokenError::AuthorityTypeNotSupported.into()),
+            }
+        }
+        Mint::LEN => {
+            initialized_mint(account)?;
+            let mut mint_data = account.try_borrow_mut_data()?;
+
+            let (offset, error) = match authority_type {
+                // MintTokens
+                0 => (MINT_AUTHORITY, TokenError::FixedSupply),
+                // FreezeAccount
+                1 => (MINT_FREEZE_AUTHORITY, TokenError::MintCannotFreeze),
+                _ => return Err(TokenError::AuthorityTypeNotSupported.into()),
+            };
+
+            let current = option_at(&mint_data, offset).ok_or(error)?;
+            validate_owner(current, authority)?;
+            set_option(&mut mint_data, offset, new_authority.as_ref());
+        }
+        _ => return Err(ProgramError::InvalidArgument),
+    }
+
+    Ok(())
+}
+
+fn mint_to(
+    mint: &AccountInfo,
+    destination: &AccountInfo,
+    authority: &AccountInfo,
+    amount: u64,
+    expected_decimals: Option<u8>,
+) -> ProgramResult {
+    let mut destination_data = token_account_mut(destination)?;
+
+    if destination_data[ACCOUNT_STATE] == AccountState::Frozen as u8 {
+        return Err(TokenError::AccountFrozen.into());
+    }
+    if native_reserve(&destination_data).is_some() {
+        return Err(TokenError::NativeNotSupported.into());
+    }
+    if mint.key() != &pubkey_at(&destination_data, ACCOUNT_MINT) {
+        return Err(TokenError::MintMismatch.into());
+    }
+
+    initialized_mint(mint)?;
+    let mut mint_data = mint.try_borrow_mut_data()?;
+
+    if let Some(expected_decimals) = expected_decimals {
+        if mint_data[MINT_DECIMALS] != expected_decimals {
+            return Err(TokenError::MintDecimalsMismatch.into());
+        }
+    }
+
+    let mint_authority = option_at(&mint_data, MINT_AUTHORITY).ok_or(TokenError::FixedSupply)?;
+    validate_owner(mint_authority, authority)?;
+
+    let destination_amount = read_u64(&destination_data, ACCOUNT_AMOUNT)?
+        .checked_add(amount)
+        .ok_or(TokenError::Overflow)?;
+    let supply = read_u64(&mint_data, MINT_SUPPLY)?
+        .checked_add(amount)
+        .ok_or(TokenError::Overflow)?;
+
+    write_u64(&mut destination_data, ACCOUNT_AMOUNT, destination_amount);
+    write_u64(&mut mint_data, MINT_SUPPLY, supply);
+
+    Ok(())
+}
+
+fn burn(
+    account: &AccountInfo,
+    mint: &AccountInfo,
+    authority: &AccountInfo,
+    amount: u64,
+    expected_decimals: Option<u8>,
+) -> ProgramResult {
+    let mut account_data = token_account_mut(account)?;
+
+    if account_data[ACCOUNT_STATE] == AccountState::Frozen as u8 {
+        return Err(TokenError::AccountFrozen.into());
+    }
+    if native_reserve(&account_data).is_some() {
+        return Err(TokenError::NativeNotSupported.into());
+    }
+    if read_u64(&account_data, ACCOUNT_AMOUNT)? < amount {
+        return Err(TokenError::InsufficientFunds.into());
+    }
+    if mint.key() != &pubkey_at(&account_data, ACCOUNT_MINT) {
+        return Err(TokenError::MintMismatch.into());
+    }
+
+    initialized_mint(mint)?;
+    let mut mint_data = mint.try_borrow_mut_data()?;
+
+    if let Some(expected_decimals) = expected_decimals {
+        if mint_data[MINT_DECIMALS] != expected_decimals {
+            return Err(TokenError::MintDecimalsMismatch.into());
+        }
+    }
+
+    validate_authority(&mut account_data, authority, amount)?;
+
+    let account_amount = read_u64(&account_data, ACCOUNT_AMOUNT)? - amount;
+    let supply = read_u64(&mint_data, MINT_SUPPLY)?
+        .checked_sub(amount)
+        .ok_or(TokenError::Overflow)?;
+
+    write_u64(&mut account_data, ACCOUNT_AMOUNT, account_amount);
+    write_u64(&mut mint_data, MINT_SUPPLY, supply);
+
+    Ok(())
+}
+
+fn close_account(
+    account: &AccountInfo,
+    destination: &AccountInfo,
+    authority: &AccountInfo,
+) -> ProgramResult {
+    if account.key() == destination.key() {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let mut account_data = token_account_mut(account)?;
+
+    if native_reserve(&account_data).is_none() && read_u64(&account_data, ACCOUNT_AMOUNT)? != 0 {
+        return Err(TokenError::NonNativeHasBalance.into());
+    }
+
+    let close_authority = option_at(&account_data, ACCOUNT_CLOSE_AUTHORITY)
+        .unwrap_or(pubkey_at(&account_data, ACCOUNT_OWNER));
+    validate_owner(close_authority, authority)?;
+
+    let destination_lamports = destination
+        .lamports()
+        .checked_add(account.lamports())
+        .ok_or(TokenError::Overflow)?;
+
+    *destination.try_borrow_mut_lamports()? = destination_lamports;
+    *account.try_borrow_mut_lamports()? = 0;
+    account_data.fill(0);
+
+    Ok(())
+}
+
+fn toggle_freeze(
+    account: &AccountInfo,
+    mint: &AccountInfo,
+    authority: &AccountInfo,
+    freeze: bool,
+) -> ProgramResult {
+    let mut account_data = token_account_mut(account)?;
+
+    if native_reserve(&account_data).is_some() {
+        return Err(TokenError::NativeNotSupported.into());
+    }
+    if mint.key() != &pubkey_at(&account_data, ACCOUNT_MINT) {
+        return Err(TokenError::MintMismatch.into());
+    }
+
+    let (current, target) = if freeze {
+        (AccountState::Initialized, AccountState::Frozen)
+    } else {
+        (AccountState::Frozen, AccountState::Initialized)
+    };
+
+    if account_data[ACCOUNT_STATE] != current as u8 {
+        return Err(TokenError::InvalidState.into());
+    }
+
+    let mint_data = initialized_mint(mint)?;
+    let freeze_authority =
+        option_at(&mint_data, MINT_FREEZE_AUTHORITY).ok_or(TokenError::MintCannotFreeze)?;
+    validate_owner(freeze_authority, authority)?;
+
+    account_data[ACCOUNT_STATE] = target as u8;
+
+    Ok(())
+}
+
+/// Validates the owner or delegate of a token account for spending `amount` tokens,
+/// updating the delegated amount when the delegate is the authority.
+fn validate_authority(
+    account_data: &mut [u8],
+    authority: &AccountInfo,
+    amount: u64,
+) -> ProgramResult {
+    match option_at(account_data, ACCOUNT_DELEGATE) {
+        Some(delegate) if authority.key() == &delegate => {
+            validate_owner(delegate, authority)?;
+
+            let delegated_amount = read_u64(account_data, ACCOUNT_DELEGATED_AMOUNT)?
+                .checked_sub(amount)
+                .ok_or(TokenError::InsufficientFunds)?;
+            write_u64(account_data, ACCOUNT_DELEGATED_AMOUNT, delegated_amount);
+
+            if delegated_amount == 0 {
+                set_option(account_data, ACCOUNT_DELEGATE, None);
+            }
+
+            Ok(())
+        }
+        _ => validate_owner(pubkey_at(account_data, ACCOUNT_OWNER), authority),
+    }
+}
+
+/// Validates that `authority` is the expected single signer authority.
+fn validate_owner(expected: Pubkey, authority: &AccountInfo) -> ProgramResult {
+    if authority.key() != &expected {
+        return Err(TokenError::OwnerMismatch.into());
+    }
+    if !authority.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    Ok(())
+}
+
+fn check_owner(account: &AccountInfo) -> ProgramResult {
+    if account.owner() != &ID {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    Ok(())
+}
+
+/// Returns the data of an initialized mint.
+fn initialized_mint(mint: &AccountInfo) -> Result<Ref<'_, [u8]>, ProgramError> {
+    check_owner(mint)?;
+    if mint.data_len() != Mint::LEN {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let data = mint.try_borrow_data()?;
+    if data[MINT_IS_INITIALIZED] != 1 {
+        return Err(ProgramError::UninitializedAccount);
+    }
+
+    Ok(data)
+}
+
+/// Returns the mutable data of an initialized token account.
+fn token_account_mut(account: &AccountInfo) -> Result<RefMut<'_, [u8]>, ProgramError> {
+    check_owner(account)?;
+    if account.data_len() != TokenAccount::LEN {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let data = account.try_borrow_mut_data()?;
+    if data[ACCOUNT_STATE] == AccountState::Uninitialized as u8 {
+        return Err(ProgramError::UninitializedAccount);
+    }
+
+    Ok(data)
+}
+
+/// Returns the rent-exempt reserve of a native token account.
+fn native_reserve(account_data: &[u8]) -> Option<u64> {
+    if account_data[ACCOUNT_IS_NATIVE] == 1 {
+        read_u64(account_data, ACCOUNT_IS_NATIVE + 4).ok()
+    } else {
+        None
+    }
+}
+
+fn read_u8(data: &[u8], offset: usize) -> Result<u8, ProgramError> {
+    data.get(offset)
+        .copied()
+        .ok_or(TokenError::InvalidInstruction.into())
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Result<u64, ProgramError> {
+    data.get(offset..offset + 8)
+        .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+        .ok_or(TokenError::InvalidInstruction.into())
+}
+
+fn read_pubkey(data: &[u8], offset: usize) -> Result<Pubkey, ProgramError> {
+    data.get(offset..offset + 32)
+        .map(|bytes| bytes.try_into().unwrap())
+        .ok_or(TokenError::InvalidInstruction.into())
+}
+
+fn pubkey_at(data: &[u8], offset: usize) -> Pubkey {
+    data[offset..offset + 32].try_into().unwrap()
+}
+
+fn write_u64(data: &mut [u8], offset: usize, value: u64) {
+    data[offset..offset + 8].copy_from_slice(&value.to_le_bytes());
+}
+
+/// Reads an optional pubkey from instruction data, prefixed by a `u8` flag.
+fn read_option(data: &[u8], offset: usize) -> Result<Option<Pubkey>, ProgramError> {
+    match read_u8(data, offset)? {
+        0 => Ok(None),
+        1 => read_pubkey(data, offset + 1).map(Some),
+        _ => Err(TokenError::InvalidInstruction.into()),
+    }
+}
+
+/// Reads an optional pubkey from account state, prefixed by a `u32` flag.
+fn option_at(data: &[u8], offset: usize) -> Option<Pubkey> {
+    (data[offset] == 1).then(|| pubkey_at(data, offset + 4))
+}
+
+/// Writes an optional pubkey to account state.
+fn set_option(data: &mut [u8], offset: usize, value: Option<&Pubkey>) {
+    data[offset..offset + 36].fill(0);
+    if let Some(value) = value {
+        data[offset] = 1;
+        data[offset + 4..offset + 36].copy_from_slice(value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::Transfer;
+    use pinocchio::{
+        instruction::{AccountMeta, Instruction},
+        testing::{default_rent, AccountBuilder, Executor, InstructionError, TestAccount},
+    };
+
+    const MINT: Pubkey = [1; 32];
+    const SOURCE: Pubkey = [2; 32];
+    const DESTINATION: Pubkey = [3; 32];
+    const OWNER: Pubkey = [4; 32];
+    const CALLER: Pubkey = [5; 32];
+    const DELEGATE: Pubkey = [6; 32];
+
+    /// Transfers tokens using the `Transfer` helper.
+    fn caller(_: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+        let [from, to, authority, ..] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        Transfer {
+            from,
+            to,
+            authority,
+            amount: u64::from_le_bytes(data.try_into().unwrap()),
+        }
+        .invoke()
+    }
+
+    fn token_account(key: Pubkey, space: usize) -> TestAccount {
+        AccountBuilder::new(key)
+            .owner(ID)
+            .lamports(default_rent().minimum_balance(space))
+            .space(space)
+            .build()
+    }
+
+    fn process(
+        executor: &Executor,
+        program_id: &Pubkey,
+        metas: &[AccountMeta],
+        data: &[u8],
+        accounts: &mut [TestAccount],
+    ) -> Result<(), InstructionError> {
+        let instruction = Instruction {
+            program_id,
+            accounts: metas,
+            data,
+        };
+        executor.process_instruction(&instruction, accounts)
+    }
+
+    /// Initializes the mint and the source and destination accounts, minting 100
+    /// tokens to the source account.
+    fn initialize(executor: &Executor, accounts: &mut [TestAccount]) {
+        // InitializeMint2
+        let mut data = [0; 67];
+        data[0] = 20;
+        data[1] = 6;
+        data[2..34].copy_from_slice(&OWNER);
+        data[34] = 1;
+        data[35..].copy_from_slice(&OWNER);
+        process(
+            executor,
+            &ID,
+            &[AccountMeta::writable(&MINT)],
+            &data,
+            accounts,
+        )
+        .unwrap();
+
+        // InitializeAccount3
+        for key in [SOURCE, DESTINATION] {
+            let mut data = [0; 33];
+            data[0] = 18;
+            data[1..].copy_from_slice(&OWNER);
+            process(
+                executor,
+                &ID,
+                &[AccountMeta::writable(&key), AccountMeta::readonly(&MINT)],
+                &data,
+                accounts,
+            )
+            .unwrap();
+        }
+
+        // MintTo
+        let mut data = [0; 9];
+        data[0] = 7;
+        data[1..].copy_from_slice(&100u64.to_le_bytes());
+        process(
+            executor,
+            &ID,
+            &[
+                AccountMeta::writable(&MINT),
+                AccountMeta::writable(&SOURCE),
+                AccountMeta::readonly_signer(&OWNER),
+            ],
+            &data,
+            accounts,
+        )
+        .unwrap();
+    }
+
+    fn token_state(account: &TestAccount) -> &TokenAccount {
+        unsafe { TokenAccount::from_bytes(account.data()) }
+    }
+
+    #[test]
+    fn test_token_flow() {
+        let mut executor = Executor::new();
+        executor
+            .add_processor(ID, process_instruction)
+            .add_processor(CALLER, caller);
+
+        let mut accounts = [
+            token_account(MINT, Mint::LEN),
+            token_account(SOURCE, TokenAccount::LEN),
+            token_account(DESTINATION, TokenAccount::LEN),
+            AccountBuilder::new(OWNER).build(),
+        ];
+        initialize(&executor, &mut accounts);
+
+        // Transfer through a cross-program invocation.
+        let metas = [
+            AccountMeta::writable(&SOURCE),
+            AccountMeta::writable(&DESTINATION),
+            AccountMeta::readonly_signer(&OWNER),
+            AccountMeta::readonly(&ID),
+        ];
+        let mut with_program = [
+            accounts[1].clone(),
+            accounts[2].clone(),
+            accounts[3].clone(),
+            AccountBuilder::new(ID).executable(true).build(),
+        ];
+        process(
+            &executor,
+            &CALLER,
+            &metas,
+            &40u64.to_le_bytes(),
+            &mut with_program,
+        )
+        .unwrap();
+
+        assert_eq!(token_state(&with_program[0]).amount(), 60);
+        assert_eq!(token_state(&with_program[1]).amount(), 40);
+
+        assert_eq!(
+            process(
+                &executor,
+                &CALLER,
+                &metas,
+                &100u64.to_le_bytes(),
+                &mut with_program,
+            ),
+            Err(InstructionError::Program(ProgramError::Custom(
+                TokenError::InsufficientFunds as u32
+            )))
+        );
+
+        // The owner must sign the transfer.
+        let metas = [
+            AccountMeta::writable(&SOURCE),
+            AccountMeta::writable(&DESTINATION),
+            AccountMeta::readonly(&OWNER),
+        ];
+        assert_eq!(
+            process(
+                &executor,
+                &CALLER,
+                &metas,
+                &10u64.to_le_bytes(),
+                &mut with_program,
+            ),
+            Err(InstructionError::PrivilegeEscalation(OWNER))
+        );
+    }
+
+    #[test]
+    fn test_approve_burn_close() {
+        let mut executor = Executor::new();
+        executor.add_processor(ID, process_instruction);
+
+        let mut accounts = [
+            token_account(MINT, Mint::LEN),
+            token_account(SOURCE, TokenAccount::LEN),
+            token_account(DESTINATION, TokenAccount::LEN),
+            AccountBuilder::new(OWNER).build(),
+            AccountBuilder::new(DELEGATE).build(),
+        ];
+        initialize(&executor, &mut accounts);
+
+        let amount_data = |discriminator: u8, amount: u64| {
+            let mut data = [0; 9];
+            data[0] = discriminator;
+            data[1..].copy_from_slice(&amount.to_le_bytes());
+            data
+        };
+
+        // Approve
+        process(
+            &executor,
+            &ID,
+            &[
+                AccountMeta::writable(&SOURCE),
+                AccountMeta::readonly(&DELEGATE),
+                AccountMeta::readonly_signer(&OWNER),
+            ],
+            &amount_data(4, 30),
+            &mut accounts,
+        )
+        .unwrap();
+
+        assert_eq!(token_state(&accounts[1]).delegate(), Some(&DELEGATE));
+        assert_eq!(token_state(&accounts[1]).delegated_amount(), 30);
+
+        // Burn by the delegate, limited to the delegated amount.
+        let burn_metas = |authority| {
+            [
+                AccountMeta::writable(&SOURCE),
+                AccountMeta::writable(&MINT),
+                AccountMeta::readonly_signer(authority),
+            ]
+        };
+        process(
+            &executor,
+            &ID,
+            &burn_metas(&DELEGATE),
+            &amount_data(8, 20),
+            &mut accounts,
+        )
+        .unwrap();
+
+        assert_eq!(token_state(&accounts[1]).amount(), 80);
+        assert_eq!(token_state(&accounts[1]).delegated_amount(), 10);
+        assert_eq!(unsafe { Mint::from_bytes(accounts[0].data()) }.supply(), 80);
+
+        assert_eq!(
+            process(
+                &executor,
+                &ID,
+                &burn_metas(&DELEGATE),
+                &amount_data(8, 20),
+                &mut accounts,
+            ),
+            Err(InstructionError::Program(ProgramError::Custom(
+                TokenError::InsufficientFunds as u32
+            )))
+        );
+
+        // CloseAccount
+        let close_metas = [
+            AccountMeta::writable(&SOURCE),
+            AccountMeta::writable(&DESTINATION),
+            AccountMeta::readonly_signer(&OWNER),
+        ];
+        assert_eq!(
+            process(&executor, &ID, &close_metas, &[9], &mut accounts),
+            Err(InstructionError::Program(ProgramError::Custom(
+                TokenError::NonNativeHasBalance as u32
+            )))
+        );
+
+        // Burn the remaining tokens by the owner.
+        process(
+            &executor,
+            &ID,
+            &burn_metas(&OWNER),
+            &amount_data(8, 80),
+            &mut accounts,
+        )
+        .unwrap();
+
+        assert_eq!(token_state(&accounts[1]).amount(), 0);
+        assert_eq!(unsafe { Mint::from_bytes(accounts[0].data()) }.supply(), 0);
+
+        let source_lamports = accounts[1].lamports();
+        let destination_lamports = accounts[2].lamports();
+
+        process(&executor, &ID, &close_metas, &[9], &mut accounts).unwrap();
+
+        assert_eq!(accounts[1].lamports(), 0);
+        assert!(accounts[1].data().iter().all(|byte| *byte == 0));
+        assert_eq!(
+            accounts[2].lamports(),
+            source_lamports + destination_lamports
+        );
+    }
+}
@@ -0,0 +1,126 @@
+//! Derive macros for Pinocchio.
+//!
+//! The macros are re-exported by `pinocchio` when the `derive` feature is enabled.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields};
+
+/// Derive the `ProgramErrorCode` trait for an enum of program-specific errors.
+///
+/// The enum can only have unit variants, and the discriminant of each variant is used
+/// as its error code. Together with the trait implementation, the macro generates:
+///
+/// * `From<Enum> for ProgramError`, which returns a `ProgramError::Custom` with the
+///   error code.
+/// * `TryFrom<u32> for Enum`, which decodes an error code and returns the code as a
+///   `ProgramError::Custom` when it does not represent any of the variants.
+///
+/// # Example
+///
+/// ```ignore
+/// use pinocchio::program_error::ProgramErrorCode;
+///
+/// #[derive(ProgramErrorCode)]
+/// pub enum VaultError {
+///     InsufficientBalance = 100,
+///     VaultLocked,
+/// }
+/// ```
+#[proc_macro_derive(ProgramErrorCode)]
+pub fn derive_program_error_code(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match expand_program_error_code(input) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+fn expand_program_error_code(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let Data::Enum(data) = &input.data else {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "ProgramErrorCode can only be derived for enums",
+        ));
+    };
+
+    let mut names = Vec::with_capacity(data.variants.len());
+    let mut codes = Vec::with_capacity(data.variants.len());
+    let mut variants = Vec::with_capacity(data.variants.len());
+
+    // Discriminant of the last variant with an explicit value and the number of
+    // variants after it, used to compute implicit discriminants.
+    let mut base = None;
+    let mut offset = 0u32;
+
+    for variant in &data.variants {
+        if !matches!(variant.fields, Fields::Unit) {
+            return Err(Error::new_spanned(
+                variant,
+                "ProgramErrorCode variants cannot have fields",
+            ));
+        }
+
+        if let Some((_, discriminant)) = &variant.discriminant {
+            base = Some(discriminant);
+            offset = 0;
+        }
+
+        codes.push(match base {
+            Some(base) => quote! { ((#base) as u32 + #offset) },
+            None => quote! { #offset },
+        });
+        names.push(variant.ident.to_string());
+        variants.push(&variant.ident);
+
+        offset += 1;
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::pinocchio::program_error::ProgramErrorCode for #ident #ty_generics #where_clause {
+            #[inline]
+            fn code(&self) -> u32 {
+                match self {
+                    #( Self::#variants => #codes, )*
+                }
+            }
+
+            #[inline]
+            fn name(&self) -> &'static str {
+                match self {
+                    #( Self::#variants => #names, )*
+                }
+            }
+
+            #[inline]
+            fn from_code(code: u32) -> ::core::option::Option<Self> {
+                #( if code == #codes { return ::core::option::Option::Some(Self::#variants); } )*
+                ::core::option::Option::None
+            }
+        }
+
+        impl #impl_generics ::core::convert::From<#ident #ty_generics> for ::pinocchio::program_error::ProgramError #where_clause {
+            #[inline]
+            fn from(error: #ident #ty_generics) -> Self {
+                ::pinocchio::program_error::ProgramError::Custom(
+                    ::pinocchio::program_error::ProgramErrorCode::code(&error),
+                )
+            }
+        }
+
+        impl #impl_generics ::core::convert::TryFrom<u32> for #ident #ty_generics #where_clause {
+            type Error = ::pinocchio::program_error::ProgramError;
+
+            #[inline]
+            fn try_from(code: u32) -> ::core::result::Result<Self, Self::Error> {
+                <Self as ::pinocchio::program_error::ProgramErrorCode>::from_code(code)
+                    .ok_or(::pinocchio::program_error::ProgramError::Custom(code))
+            }
+        }
+    })
+}
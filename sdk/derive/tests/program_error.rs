@@ -0,0 +1,101 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    program_entrypoint,
+    program_error::{log_error, ProgramError, ProgramErrorCode},
+    pubkey::Pubkey,
+    testing::{serialize, RecordingStubs},
+    ProgramResult,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ProgramErrorCode)]
+pub enum VaultError {
+    NotInitialized,
+    InsufficientBalance = 100,
+    VaultLocked,
+    InvalidAuthority = 200,
+}
+
+#[test]
+fn test_error_codes() {
+    assert_eq!(VaultError::NotInitialized.code(), 0);
+    assert_eq!(VaultError::InsufficientBalance.code(), 100);
+    assert_eq!(VaultError::VaultLocked.code(), 101);
+    assert_eq!(VaultError::InvalidAuthority.code(), 200);
+
+    assert_eq!(VaultError::VaultLocked.name(), "VaultLocked");
+
+    let error: ProgramError = VaultError::VaultLocked.into();
+    assert_eq!(error, ProgramError::Custom(101));
+
+    assert_eq!(VaultError::try_from(200), Ok(VaultError::InvalidAuthority));
+    assert_eq!(VaultError::try_from(102), Err(ProgramError::Custom(102)));
+}
+
+/// Errors whose variants shadow the prelude names used by the derive.
+mod shadowed {
+    use pinocchio::program_error::ProgramErrorCode;
+
+    #[allow(dead_code)]
+    type Option<T> = core::result::Result<T, ()>;
+    #[allow(dead_code)]
+    type Result<T, E> = core::option::Option<(T, E)>;
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, ProgramErrorCode)]
+    pub enum ShadowedError {
+        Some,
+        None,
+    }
+
+    #[allow(unused_imports)]
+    use ShadowedError::*;
+}
+
+#[test]
+fn test_shadowed_names() {
+    use shadowed::ShadowedError;
+
+    assert_eq!(ShadowedError::from_code(1), Some(ShadowedError::None));
+    assert_eq!(ShadowedError::try_from(0), Ok(ShadowedError::Some));
+    assert_eq!(ShadowedError::from_code(2), None);
+}
+
+#[test]
+fn test_log_error() {
+    let stubs = RecordingStubs::install();
+
+    log_error::<VaultError>(&VaultError::InsufficientBalance.into());
+    // Errors that do not represent a `VaultError` are not logged.
+    log_error::<VaultError>(&ProgramError::Custom(7));
+    log_error::<VaultError>(&ProgramError::InvalidArgument);
+
+    assert_eq!(stubs.logs(), ["Error: InsufficientBalance (100)"]);
+}
+
+program_entrypoint!(process_instruction, 4, error = VaultError);
+
+fn process_instruction(
+    _program_id: &Pubkey,
+    _accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    match instruction_data {
+        [] => Ok(()),
+        _ => Err(VaultError::VaultLocked.into()),
+    }
+}
+
+#[test]
+fn test_entrypoint_logs_error() {
+    let stubs = RecordingStubs::install();
+
+    let mut input = serialize(&[1; 32], &[], &[]);
+    assert_eq!(
+        unsafe { entrypoint(input.as_mut_ptr()) },
+        pinocchio::SUCCESS
+    );
+    assert!(stubs.logs().is_empty());
+
+    let mut input = serialize(&[1; 32], &[], &[1]);
+    assert_eq!(unsafe { entrypoint(input.as_mut_ptr()) }, 101);
+    assert_eq!(stubs.logs(), ["Error: VaultLocked (101)"]);
+}
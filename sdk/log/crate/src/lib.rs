@@ -86,6 +86,23 @@ mod tests {
         assert!(&*logger == "balance=100@".as_bytes());
     }
 
+    #[test]
+    fn test_logger_write() {
+        use core::fmt::Write;
+
+        let mut logger = Logger::<100>::default();
+        let file = "file.rs";
+        write!(logger, "{}:{}", file, 10).unwrap();
+
+        assert!(&*logger == "file.rs:10".as_bytes());
+
+        let mut logger = Logger::<8>::default();
+        let (hello, world) = ("Hello ", "world!");
+        write!(logger, "{}{}", hello, world).unwrap();
+
+        assert!(&*logger == "Hello w@".as_bytes());
+    }
+
     #[test]
     fn test_logger_slice() {
         let mut logger = Logger::<20>::default();
@@ -115,6 +115,19 @@ impl<const BUFFER: usize> Logger<BUFFER> {
     }
 }
 
+impl<const BUFFER: usize> core::fmt::Write for Logger<BUFFER> {
+    /// Append a string to the logger.
+    ///
+    /// This allows using the logger with the `write!` macro to format values that
+    /// implement `core::fmt::Display`. The message is truncated when the buffer is
+    /// full, so writing never fails.
+    #[inline]
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        self.append(s);
+        Ok(())
+    }
+}
+
 /// Log a message.
 #[inline(always)]
 pub fn log_message(message: &[u8]) {
@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use pinocchio::testing::fuzz::BorrowModel;
+
+fuzz_target!(|model: BorrowModel| {
+    model.check();
+});
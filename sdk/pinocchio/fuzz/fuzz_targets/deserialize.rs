@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use pinocchio::{testing::fuzz::InputModel, MAX_TX_ACCOUNTS};
+
+fuzz_target!(|model: InputModel| {
+    model.check_deserialize::<MAX_TX_ACCOUNTS>();
+    // A smaller limit exercises skipping the accounts that are not processed.
+    model.check_deserialize::<4>();
+});
@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use pinocchio::testing::fuzz::InputModel;
+
+fuzz_target!(|model: InputModel| {
+    model.check_lazy();
+});
@@ -9,12 +9,21 @@ use core::{
 #[cfg(target_os = "solana")]
 use crate::syscalls::sol_memset_;
 
-use crate::{program_error::ProgramError, pubkey::Pubkey, ProgramResult};
+use crate::{program_error::ProgramError, pubkey::Pubkey, sysvars::rent::Rent, ProgramResult};
+
+#[cfg(feature = "borrow-debug")]
+mod borrow_debug;
+
+#[cfg(feature = "borrow-debug")]
+use borrow_debug::BorrowKind;
 
 /// Maximum number of bytes a program may add to an account during a
 /// single realloc.
 pub const MAX_PERMITTED_DATA_INCREASE: usize = 1_024 * 10;
 
+/// Address of the System program, which owns closed accounts.
+const SYSTEM_PROGRAM_ID: Pubkey = [0; 32];
+
 /// Returns the account info at the given index.
 ///
 /// This macro validates that the index is within the bounds of the accounts.
@@ -48,13 +57,13 @@ pub(crate) struct Account {
     pub(crate) borrow_state: u8,
 
     /// Indicates whether the transaction was signed by this account.
-    is_signer: u8,
+    pub(crate) is_signer: u8,
 
     /// Indicates whether the account is writable.
-    is_writable: u8,
+    pub(crate) is_writable: u8,
 
     /// Indicates whether this account represents a program.
-    executable: u8,
+    pub(crate) executable: u8,
 
     /// Account's original data length when it was serialized for the
     /// current program invocation.
@@ -67,16 +76,16 @@ pub(crate) struct Account {
     /// Currently, this value is only used for `realloc` to determine if the
     /// account data length has changed from the original serialized length beyond
     /// the maximum permitted data increase.
-    original_data_len: u32,
+    pub(crate) original_data_len: u32,
 
     /// Public key of the account
-    key: Pubkey,
+    pub(crate) key: Pubkey,
 
     /// Program that owns this account
-    owner: Pubkey,
+    pub(crate) owner: Pubkey,
 
     /// The lamports in the account.  Modifiable by programs.
-    lamports: u64,
+    pub(crate) lamports: u64,
 
     /// Length of the data.
     pub(crate) data_len: u64,
@@ -96,6 +105,36 @@ const SET_LEN_MASK: u32 = 1 << 31;
 /// by clearing the flag that indicates the original data length has been set.
 const GET_LEN_MASK: u32 = !SET_LEN_MASK;
 
+/// Borrow state of the lamports and data of an account.
+///
+/// The state is shared by all `AccountInfo` instances of the same account, so
+/// it includes the borrows made through duplicated accounts.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct BorrowState {
+    /// Number of immutable borrows of the lamports.
+    pub lamports_borrows: u8,
+
+    /// Indicates whether the lamports are mutably borrowed.
+    pub lamports_borrowed_mut: bool,
+
+    /// Number of immutable borrows of the data.
+    pub data_borrows: u8,
+
+    /// Indicates whether the data is mutably borrowed.
+    pub data_borrowed_mut: bool,
+}
+
+impl BorrowState {
+    /// Indicates whether the lamports or data are borrowed in any form.
+    #[inline(always)]
+    pub fn is_borrowed(&self) -> bool {
+        self.lamports_borrows > 0
+            || self.lamports_borrowed_mut
+            || self.data_borrows > 0
+            || self.data_borrowed_mut
+    }
+}
+
 /// Wrapper struct for an `Account`.
 ///
 /// This struct provides safe access to the data in an `Account`. It is also
@@ -155,6 +194,22 @@ impl AccountInfo {
         unsafe { (*self.raw).lamports }
     }
 
+    /// Returns the current borrow state of the lamports and data.
+    ///
+    /// This can be used to find which borrow is still alive when a borrow fails
+    /// with [`ProgramError::AccountBorrowFailed`].
+    #[inline(always)]
+    pub fn borrow_state(&self) -> BorrowState {
+        let state = unsafe { (*self.raw).borrow_state };
+
+        BorrowState {
+            lamports_borrows: (state >> LAMPORTS_SHIFT) & 0b_0111,
+            lamports_borrowed_mut: state & 0b_1000_0000 != 0,
+            data_borrows: (state >> DATA_SHIFT) & 0b_0111,
+            data_borrowed_mut: state & 0b_0000_1000 != 0,
+        }
+    }
+
     /// Indicates whether the account data is empty.
     ///
     /// An account is considered empty if the data length is zero.
@@ -218,12 +273,45 @@ impl AccountInfo {
         core::slice::from_raw_parts_mut(self.data_ptr(), self.data_len())
     }
 
+    /// Checks a borrow with `check`, recording the location of the caller when the
+    /// borrow is possible and logging the conflicting borrows when it is not.
+    ///
+    /// Returns the id of the borrow record.
+    #[cfg(feature = "borrow-debug")]
+    #[track_caller]
+    fn track_borrow(
+        &self,
+        kind: BorrowKind,
+        mutable: bool,
+        check: fn(&Self) -> Result<(), ProgramError>,
+    ) -> Result<usize, ProgramError> {
+        let location = core::panic::Location::caller();
+
+        if let Err(error) = check(self) {
+            borrow_debug::log_conflicts(self.raw as *const u8, kind, location);
+            return Err(error);
+        }
+
+        Ok(borrow_debug::record(
+            self.raw as *const u8,
+            kind,
+            mutable,
+            location,
+        ))
+    }
+
     /// Tries to get a read-only reference to the lamport field, failing if the
     /// field is already mutable borrowed or if 7 borrows already exist.
+    #[cfg_attr(feature = "borrow-debug", track_caller)]
     pub fn try_borrow_lamports(&self) -> Result<Ref<u64>, ProgramError> {
         // check if the account lamports are already borrowed
+        #[cfg(not(feature = "borrow-debug"))]
         self.check_borrow_lamports()?;
 
+        #[cfg(feature = "borrow-debug")]
+        let borrow_id =
+            self.track_borrow(BorrowKind::Lamports, false, Self::check_borrow_lamports)?;
+
         let borrow_state = unsafe { &mut (*self.raw).borrow_state };
         // increment the immutable borrow count
         *borrow_state += 1 << LAMPORTS_SHIFT;
@@ -233,16 +321,24 @@ impl AccountInfo {
             value: unsafe { NonNull::from(&(*self.raw).lamports) },
             state: unsafe { NonNull::new_unchecked(borrow_state) },
             borrow_shift: LAMPORTS_SHIFT,
+            #[cfg(feature = "borrow-debug")]
+            borrow_id,
             marker: PhantomData,
         })
     }
 
     /// Tries to get a read only reference to the lamport field, failing if the field
     /// is already borrowed in any form.
+    #[cfg_attr(feature = "borrow-debug", track_caller)]
     pub fn try_borrow_mut_lamports(&self) -> Result<RefMut<u64>, ProgramError> {
         // check if the account lamports are already borrowed
+        #[cfg(not(feature = "borrow-debug"))]
         self.check_borrow_mut_lamports()?;
 
+        #[cfg(feature = "borrow-debug")]
+        let borrow_id =
+            self.track_borrow(BorrowKind::Lamports, true, Self::check_borrow_mut_lamports)?;
+
         let borrow_state = unsafe { &mut (*self.raw).borrow_state };
         // set the mutable lamport borrow flag
         *borrow_state |= 0b_1000_0000;
@@ -252,6 +348,8 @@ impl AccountInfo {
             value: unsafe { NonNull::from(&mut (*self.raw).lamports) },
             state: unsafe { NonNull::new_unchecked(borrow_state) },
             borrow_mask: LAMPORTS_MASK,
+            #[cfg(feature = "borrow-debug")]
+            borrow_id,
             marker: PhantomData,
         })
     }
@@ -291,10 +389,15 @@ impl AccountInfo {
 
     /// Tries to get a read-only reference to the data field, failing if the field
     /// is already mutable borrowed or if 7 borrows already exist.
+    #[cfg_attr(feature = "borrow-debug", track_caller)]
     pub fn try_borrow_data(&self) -> Result<Ref<[u8]>, ProgramError> {
         // check if the account data is already borrowed
+        #[cfg(not(feature = "borrow-debug"))]
         self.check_borrow_data()?;
 
+        #[cfg(feature = "borrow-debug")]
+        let borrow_id = self.track_borrow(BorrowKind::Data, false, Self::check_borrow_data)?;
+
         let borrow_state = unsafe { &mut (*self.raw).borrow_state };
         // increment the immutable data borrow count
         *borrow_state += 1;
@@ -304,16 +407,23 @@ impl AccountInfo {
             value: unsafe { NonNull::from(from_raw_parts(self.data_ptr(), self.data_len())) },
             state: unsafe { NonNull::new_unchecked(borrow_state) },
             borrow_shift: DATA_SHIFT,
+            #[cfg(feature = "borrow-debug")]
+            borrow_id,
             marker: PhantomData,
         })
     }
 
     /// Tries to get a mutable reference to the data field, failing if the field
     /// is already borrowed in any form.
+    #[cfg_attr(feature = "borrow-debug", track_caller)]
     pub fn try_borrow_mut_data(&self) -> Result<RefMut<[u8]>, ProgramError> {
         // check if the account data is already borrowed
+        #[cfg(not(feature = "borrow-debug"))]
         self.check_borrow_mut_data()?;
 
+        #[cfg(feature = "borrow-debug")]
+        let borrow_id = self.track_borrow(BorrowKind::Data, true, Self::check_borrow_mut_data)?;
+
         let borrow_state = unsafe { &mut (*self.raw).borrow_state };
         // set the mutable data borrow flag
         *borrow_state |= 0b_0000_1000;
@@ -323,6 +433,8 @@ impl AccountInfo {
             value: unsafe { NonNull::from(from_raw_parts_mut(self.data_ptr(), self.data_len())) },
             state: unsafe { NonNull::new_unchecked(borrow_state) },
             borrow_mask: DATA_MASK,
+            #[cfg(feature = "borrow-debug")]
+            borrow_id,
             marker: PhantomData,
         })
     }
@@ -436,6 +548,105 @@ impl AccountInfo {
         Ok(())
     }
 
+    /// Transfer lamports from this account to the `to` account.
+    ///
+    /// This account must be owned by `program_id`, since only the owner of an
+    /// account can debit its lamports, and both accounts must be writable. When
+    /// `rent` is specified, the remaining balance of this account must keep it
+    /// rent-exempt.
+    ///
+    /// Returns [`ProgramError::InsufficientFunds`] when the account does not have
+    /// enough lamports and [`ProgramError::ArithmeticOverflow`] when the balance of
+    /// the `to` account overflows.
+    pub fn transfer_lamports(
+        &self,
+        to: &AccountInfo,
+        amount: u64,
+        program_id: &Pubkey,
+        rent: Option<&Rent>,
+    ) -> ProgramResult {
+        if !self.is_writable() || !to.is_writable() {
+            return Err(ProgramError::Immutable);
+        }
+
+        if self.owner() != program_id {
+            return Err(ProgramError::InvalidAccountOwner);
+        }
+
+        let mut from_lamports = self.try_borrow_mut_lamports()?;
+        let remaining = from_lamports
+            .checked_sub(amount)
+            .ok_or(ProgramError::InsufficientFunds)?;
+
+        // a transfer to the same account does not change its balance
+        if self.key() == to.key() {
+            return Ok(());
+        }
+
+        if let Some(rent) = rent {
+            if !rent.is_exempt(remaining, self.data_len()) {
+                return Err(ProgramError::AccountNotRentExempt);
+            }
+        }
+
+        let mut to_lamports = to.try_borrow_mut_lamports()?;
+        *to_lamports = to_lamports
+            .checked_add(amount)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        *from_lamports = remaining;
+
+        Ok(())
+    }
+
+    /// Close the account, moving all of its lamports to the `destination` account.
+    ///
+    /// The account data is resized to zero and the account is assigned to the System
+    /// program. When a `closed_discriminator` is specified, the data is instead resized
+    /// to its length and set to its value, which allows a program to reject an account
+    /// revived within the same transaction &mdash; e.g., by a transfer of lamports after
+    /// the account is closed. In this case, the account keeps its current owner, since
+    /// the runtime only allows the owner to change when the account data is zeroed.
+    ///
+    /// Both accounts must be writable and they cannot be the same account. Returns
+    /// [`ProgramError::ArithmeticOverflow`] when the balance of the `destination`
+    /// account overflows.
+    pub fn close_to(
+        &self,
+        destination: &AccountInfo,
+        closed_discriminator: Option<&[u8]>,
+    ) -> ProgramResult {
+        if !self.is_writable() || !destination.is_writable() {
+            return Err(ProgramError::Immutable);
+        }
+
+        if self.key() == destination.key() {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        {
+            let mut lamports = self.try_borrow_mut_lamports()?;
+            let mut destination_lamports = destination.try_borrow_mut_lamports()?;
+
+            *destination_lamports = destination_lamports
+                .checked_add(*lamports)
+                .ok_or(ProgramError::ArithmeticOverflow)?;
+            *lamports = 0;
+        }
+
+        match closed_discriminator {
+            Some(discriminator) => {
+                self.realloc(discriminator.len(), false)?;
+                self.try_borrow_mut_data()?.copy_from_slice(discriminator);
+            }
+            None => {
+                self.realloc(0, false)?;
+                self.assign(&SYSTEM_PROGRAM_ID);
+            }
+        }
+
+        Ok(())
+    }
+
     /// Zero out the the account's data length, lamports and owner fields, effectively
     /// closing the account.
     ///
@@ -483,6 +694,9 @@ impl AccountInfo {
         // So we can zero out them directly.
         #[cfg(target_os = "solana")]
         sol_memset_(self.data_ptr().sub(48), 0, 48);
+
+        #[cfg(not(target_os = "solana"))]
+        core::ptr::write_bytes(self.data_ptr().sub(48), 0, 48);
     }
 
     /// Returns the memory address of the account data.
@@ -504,6 +718,9 @@ pub struct Ref<'a, T: ?Sized> {
     /// Indicates the type of borrow (lamports or data) by representing the
     /// shift amount.
     borrow_shift: u8,
+    /// Id of the borrow record released on drop.
+    #[cfg(feature = "borrow-debug")]
+    borrow_id: usize,
     /// The `value` raw pointer is only valid while the `&'a T` lives so we claim
     /// to hold a reference to it.
     marker: PhantomData<&'a T>,
@@ -523,6 +740,8 @@ impl<'a, T: ?Sized> Ref<'a, T> {
             value: NonNull::from(f(&*orig)),
             state: orig.state,
             borrow_shift: orig.borrow_shift,
+            #[cfg(feature = "borrow-debug")]
+            borrow_id: orig.borrow_id,
             marker: PhantomData,
         }
     }
@@ -541,6 +760,8 @@ impl<'a, T: ?Sized> Ref<'a, T> {
                 value: NonNull::from(value),
                 state: orig.state,
                 borrow_shift: orig.borrow_shift,
+                #[cfg(feature = "borrow-debug")]
+                borrow_id: orig.borrow_id,
                 marker: PhantomData,
             }),
             None => Err(ManuallyDrop::into_inner(orig)),
@@ -559,6 +780,9 @@ impl<T: ?Sized> Drop for Ref<'_, T> {
     // decrement the immutable borrow count
     fn drop(&mut self) {
         unsafe { *self.state.as_mut() -= 1 << self.borrow_shift };
+
+        #[cfg(feature = "borrow-debug")]
+        borrow_debug::release(self.borrow_id);
     }
 }
 
@@ -575,6 +799,9 @@ pub struct RefMut<'a, T: ?Sized> {
     /// Indicates the type of borrow (lamports or data) by representing the
     /// mutable borrow mask.
     borrow_mask: u8,
+    /// Id of the borrow record released on drop.
+    #[cfg(feature = "borrow-debug")]
+    borrow_id: usize,
     /// The `value` raw pointer is only valid while the `&'a T` lives so we claim
     /// to hold a reference to it.
     marker: PhantomData<&'a mut T>,
@@ -594,6 +821,8 @@ impl<'a, T: ?Sized> RefMut<'a, T> {
             value: NonNull::from(f(&mut *orig)),
             state: orig.state,
             borrow_mask: orig.borrow_mask,
+            #[cfg(feature = "borrow-debug")]
+            borrow_id: orig.borrow_id,
             marker: PhantomData,
         }
     }
@@ -614,6 +843,8 @@ impl<'a, T: ?Sized> RefMut<'a, T> {
                     value,
                     state: orig.state,
                     borrow_mask: orig.borrow_mask,
+                    #[cfg(feature = "borrow-debug")]
+                    borrow_id: orig.borrow_id,
                     marker: PhantomData,
                 })
             }
@@ -638,6 +869,9 @@ impl<T: ?Sized> Drop for RefMut<'_, T> {
     fn drop(&mut self) {
         // unset the mutable borrow flag
         unsafe { *self.state.as_mut() &= self.borrow_mask };
+
+        #[cfg(feature = "borrow-debug")]
+        borrow_debug::release(self.borrow_id);
     }
 }
 
@@ -654,6 +888,8 @@ mod tests {
             value: NonNull::from(&data),
             borrow_shift: DATA_SHIFT,
             state: NonNull::from(&state),
+            #[cfg(feature = "borrow-debug")]
+            borrow_id: 0,
             marker: PhantomData,
         };
 
@@ -688,6 +924,8 @@ mod tests {
             value: NonNull::from(&lamports),
             borrow_shift: LAMPORTS_SHIFT,
             state: NonNull::from(&state),
+            #[cfg(feature = "borrow-debug")]
+            borrow_id: 0,
             marker: PhantomData,
         };
 
@@ -722,6 +960,8 @@ mod tests {
             value: NonNull::from(&data),
             borrow_mask: DATA_MASK,
             state: NonNull::from(&state),
+            #[cfg(feature = "borrow-debug")]
+            borrow_id: 0,
             marker: PhantomData,
         };
 
@@ -749,6 +989,8 @@ mod tests {
             value: NonNull::from(&lamports),
             borrow_mask: LAMPORTS_MASK,
             state: NonNull::from(&state),
+            #[cfg(feature = "borrow-debug")]
+            borrow_id: 0,
             marker: PhantomData,
         };
 
@@ -765,4 +1007,265 @@ mod tests {
         assert_eq!(lamports, 200);
         assert_eq!(state, 0);
     }
+
+    #[test]
+    fn test_transfer_lamports() {
+        use crate::testing::{default_rent, AccountBuilder};
+
+        let program_id = [9; 32];
+        let from = AccountBuilder::new([1; 32])
+            .owner(program_id)
+            .lamports(1_000_000)
+            .writable(true)
+            .build();
+        let to = AccountBuilder::new([2; 32])
+            .lamports(u64::MAX - 10)
+            .writable(true)
+            .build();
+        let other = AccountBuilder::new([3; 32]).writable(true).build();
+        let readonly = AccountBuilder::new([4; 32]).build();
+
+        assert_eq!(
+            from.transfer_lamports(&readonly, 1, &program_id, None),
+            Err(ProgramError::Immutable)
+        );
+        assert_eq!(
+            to.transfer_lamports(&other, 1, &program_id, None),
+            Err(ProgramError::InvalidAccountOwner)
+        );
+        assert_eq!(
+            from.transfer_lamports(&other, 1_000_001, &program_id, None),
+            Err(ProgramError::InsufficientFunds)
+        );
+        assert_eq!(
+            from.transfer_lamports(&to, 11, &program_id, None),
+            Err(ProgramError::ArithmeticOverflow)
+        );
+
+        // the remaining balance must keep the account rent-exempt
+        let rent = default_rent();
+        let minimum_balance = rent.minimum_balance(from.data_len());
+        assert_eq!(
+            from.transfer_lamports(
+                &other,
+                1_000_000 - minimum_balance + 1,
+                &program_id,
+                Some(&rent)
+            ),
+            Err(ProgramError::AccountNotRentExempt)
+        );
+
+        from.transfer_lamports(
+            &other,
+            1_000_000 - minimum_balance,
+            &program_id,
+            Some(&rent),
+        )
+        .unwrap();
+        assert_eq!(from.lamports(), minimum_balance);
+        assert_eq!(other.lamports(), 1_000_000 - minimum_balance);
+
+        // a transfer to the same account keeps the balance
+        from.transfer_lamports(&from, 10, &program_id, None)
+            .unwrap();
+        assert_eq!(from.lamports(), minimum_balance);
+    }
+
+    #[test]
+    fn test_close_to() {
+        use crate::testing::AccountBuilder;
+
+        let account = AccountBuilder::new([1; 32])
+            .owner([9; 32])
+            .lamports(1_000)
+            .data(&[1; 16])
+            .writable(true)
+            .build();
+        let destination = AccountBuilder::new([2; 32])
+            .lamports(u64::MAX - 10)
+            .writable(true)
+            .build();
+        let readonly = AccountBuilder::new([3; 32]).build();
+
+        assert_eq!(
+            account.close_to(&readonly, None),
+            Err(ProgramError::Immutable)
+        );
+        assert_eq!(
+            account.close_to(&account, None),
+            Err(ProgramError::InvalidArgument)
+        );
+        assert_eq!(
+            account.close_to(&destination, None),
+            Err(ProgramError::ArithmeticOverflow)
+        );
+        assert_eq!(account.lamports(), 1_000);
+
+        let destination = AccountBuilder::new([2; 32])
+            .lamports(500)
+            .writable(true)
+            .build();
+        account.close_to(&destination, None).unwrap();
+
+        assert_eq!(account.lamports(), 0);
+        assert_eq!(account.data_len(), 0);
+        assert_eq!(account.owner(), &SYSTEM_PROGRAM_ID);
+        assert_eq!(destination.lamports(), 1_500);
+
+        // closed discriminator
+        let account = AccountBuilder::new([1; 32])
+            .owner([9; 32])
+            .lamports(1_000)
+            .data(&[1; 16])
+            .writable(true)
+            .build();
+        account.close_to(&destination, Some(&[255; 8])).unwrap();
+
+        assert_eq!(account.lamports(), 0);
+        assert_eq!(account.data(), &[255; 8]);
+        assert_eq!(account.owner(), &[9; 32]);
+        assert_eq!(destination.lamports(), 2_500);
+    }
+
+    #[test]
+    fn test_close_to_verified() {
+        use crate::{
+            instruction::{AccountMeta, Instruction},
+            testing::{AccountBuilder, Executor},
+        };
+
+        const PROGRAM: Pubkey = [9; 32];
+
+        fn close(_: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+            let discriminator = (!data.is_empty()).then_some(data);
+            accounts[0].close_to(&accounts[1], discriminator)
+        }
+
+        let mut executor = Executor::new();
+        executor.add_processor(PROGRAM, close);
+
+        let metas = [
+            AccountMeta::writable(&[1; 32]),
+            AccountMeta::writable(&[2; 32]),
+        ];
+
+        // The changes are accepted by the same checks the runtime performs.
+        for discriminator in [&[][..], &[255; 8]] {
+            let mut accounts = [
+                AccountBuilder::new([1; 32])
+                    .owner(PROGRAM)
+                    .lamports(1_000)
+                    .data(&[1; 16])
+                    .build(),
+                AccountBuilder::new([2; 32]).build(),
+            ];
+            let instruction = Instruction {
+                program_id: &PROGRAM,
+                accounts: &metas,
+                data: discriminator,
+            };
+            executor
+                .process_instruction(&instruction, &mut accounts)
+                .unwrap();
+
+            assert_eq!(accounts[0].data(), discriminator);
+            assert_eq!(accounts[1].lamports(), 1_000);
+        }
+    }
+
+    #[test]
+    fn test_borrow_state() {
+        use crate::testing::AccountBuilder;
+
+        let account = AccountBuilder::new([1; 32]).build();
+        assert!(!account.borrow_state().is_borrowed());
+
+        let lamports = account.try_borrow_lamports().unwrap();
+        let other = account.try_borrow_lamports().unwrap();
+        let data = account.try_borrow_mut_data().unwrap();
+
+        assert_eq!(
+            account.borrow_state(),
+            BorrowState {
+                lamports_borrows: 2,
+                lamports_borrowed_mut: false,
+                data_borrows: 0,
+                data_borrowed_mut: true,
+            }
+        );
+
+        drop((lamports, other, data));
+
+        let lamports = account.try_borrow_mut_lamports().unwrap();
+        let data = account.try_borrow_data().unwrap();
+
+        assert_eq!(
+            account.borrow_state(),
+            BorrowState {
+                lamports_borrows: 0,
+                lamports_borrowed_mut: true,
+                data_borrows: 1,
+                data_borrowed_mut: false,
+            }
+        );
+
+        drop((lamports, data));
+        assert!(!account.borrow_state().is_borrowed());
+    }
+
+    #[cfg(feature = "borrow-debug")]
+    #[test]
+    fn test_borrow_debug() {
+        use crate::testing::{AccountBuilder, RecordingStubs};
+
+        let stubs = RecordingStubs::install();
+        let account = AccountBuilder::new([1; 32]).build();
+
+        let line = line!() + 1;
+        let data = account.try_borrow_mut_data().unwrap();
+        let lamports = account.try_borrow_lamports().unwrap();
+
+        assert!(account.try_borrow_data().is_err());
+        drop(lamports);
+
+        let logs = stubs.logs();
+        assert_eq!(logs.len(), 2);
+        assert!(logs[0].starts_with("Account borrow failed at "));
+        assert!(logs[1].starts_with("- Data borrowed mutably at "));
+        assert!(logs[1].contains(&std::format!("account_info.rs:{line}:")));
+
+        // released borrows are not logged
+        stubs.clear();
+        drop(data);
+        let data = account.try_borrow_data().unwrap();
+        assert!(account.try_borrow_mut_lamports().is_ok());
+        assert!(account.try_borrow_mut_data().is_err());
+
+        let logs = stubs.logs();
+        assert_eq!(logs.len(), 2);
+        assert!(logs[1].starts_with("- Data borrowed at "));
+        drop(data);
+    }
+
+    #[cfg(feature = "borrow-debug")]
+    #[test]
+    fn test_borrow_debug_out_of_order() {
+        use crate::testing::{AccountBuilder, RecordingStubs};
+
+        let stubs = RecordingStubs::install();
+        let account = AccountBuilder::new([1; 32]).build();
+
+        let first = account.try_borrow_data().unwrap();
+        let line = line!() + 1;
+        let second = account.try_borrow_data().unwrap();
+
+        // dropping the first borrow releases its own record, not the most recent one
+        drop(first);
+        assert!(account.try_borrow_mut_data().is_err());
+
+        let logs = stubs.logs();
+        assert_eq!(logs.len(), 2);
+        assert!(logs[1].contains(&std::format!("account_info.rs:{line}:")));
+        drop(second);
+    }
 }
@@ -0,0 +1,159 @@
+//! Tracking of the source location of account borrows.
+//!
+//! Each successful borrow records the location of the `try_borrow*` call that
+//! created it under a unique id, which is kept by the `Ref` or `RefMut` and used
+//! to remove the record when that borrow is dropped. When a borrow fails,
+//! the locations of the borrows still alive for the account are logged.
+//!
+//! Programs cannot have mutable static data on-chain, so the borrows are only
+//! tracked in host builds.
+
+use core::panic::Location;
+
+/// Field of an account that is borrowed.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(super) enum BorrowKind {
+    Lamports,
+    Data,
+}
+
+#[cfg(not(target_os = "solana"))]
+mod tracker {
+    use core::{
+        cell::{Cell, RefCell},
+        panic::Location,
+    };
+    use std::{format, vec::Vec};
+
+    use super::BorrowKind;
+
+    /// A borrow still alive.
+    struct Borrow {
+        /// Id of the borrow.
+        id: usize,
+
+        /// Address of the borrow state of the account.
+        account: usize,
+
+        /// Field borrowed.
+        kind: BorrowKind,
+
+        /// Indicates whether the borrow is mutable.
+        mutable: bool,
+
+        /// Location where the borrow was created.
+        location: &'static Location<'static>,
+    }
+
+    std::thread_local! {
+        /// Borrows alive in the current thread.
+        static BORROWS: RefCell<Vec<Borrow>> = const { RefCell::new(Vec::new()) };
+
+        /// Id of the next borrow recorded in the current thread.
+        static NEXT_ID: Cell<usize> = const { Cell::new(1) };
+    }
+
+    pub(crate) fn record(
+        account: *const u8,
+        kind: BorrowKind,
+        mutable: bool,
+        location: &'static Location<'static>,
+    ) -> usize {
+        let id = NEXT_ID.with(|next| next.replace(next.get() + 1));
+
+        BORROWS.with(|borrows| {
+            borrows.borrow_mut().push(Borrow {
+                id,
+                account: account as usize,
+                kind,
+                mutable,
+                location,
+            })
+        });
+
+        id
+    }
+
+    pub(crate) fn release(id: usize) {
+        BORROWS.with(|borrows| {
+            let mut borrows = borrows.borrow_mut();
+
+            if let Some(position) = borrows.iter().position(|borrow| borrow.id == id) {
+                borrows.remove(position);
+            }
+        });
+    }
+
+    pub(crate) fn log_conflicts(
+        account: *const u8,
+        kind: BorrowKind,
+        location: &'static Location<'static>,
+    ) {
+        crate::log::sol_log(&format!("Account borrow failed at {location}"));
+
+        BORROWS.with(|borrows| {
+            for borrow in borrows
+                .borrow()
+                .iter()
+                .filter(|borrow| borrow.account == account as usize && borrow.kind == kind)
+            {
+                crate::log::sol_log(&format!(
+                    "- {:?} borrowed{} at {}",
+                    borrow.kind,
+                    if borrow.mutable { " mutably" } else { "" },
+                    borrow.location
+                ));
+            }
+        });
+    }
+}
+
+/// Records a borrow created at `location`, returning the id to release it with.
+#[inline(always)]
+pub(super) fn record(
+    account: *const u8,
+    kind: BorrowKind,
+    mutable: bool,
+    location: &'static Location<'static>,
+) -> usize {
+    #[cfg(not(target_os = "solana"))]
+    return tracker::record(account, kind, mutable, location);
+
+    #[cfg(target_os = "solana")]
+    {
+        let _ = (account, kind, mutable, location);
+        0
+    }
+}
+
+/// Removes the borrow with the given id.
+#[inline(always)]
+pub(super) fn release(id: usize) {
+    #[cfg(not(target_os = "solana"))]
+    tracker::release(id);
+
+    #[cfg(target_os = "solana")]
+    let _ = id;
+}
+
+/// Logs the location of a failed borrow and of the borrows it conflicts with.
+///
+/// The borrows are not tracked on-chain, so only the location of the failed
+/// borrow is logged there.
+#[cold]
+pub(super) fn log_conflicts(
+    account: *const u8,
+    kind: BorrowKind,
+    location: &'static Location<'static>,
+) {
+    #[cfg(not(target_os = "solana"))]
+    tracker::log_conflicts(account, kind, location);
+
+    #[cfg(target_os = "solana")]
+    {
+        let _ = (account, kind);
+        crate::log::sol_log("Account borrow failed at");
+        crate::log::sol_log(location.file());
+        crate::log::sol_log_64(0, 0, 0, location.line() as u64, location.column() as u64);
+    }
+}
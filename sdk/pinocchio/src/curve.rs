@@ -0,0 +1,204 @@
+//! Minimal ed25519 point validation used on host builds.
+//!
+//! Field elements are represented as four little-endian `u64` limbs modulo
+//! `p = 2^255 - 19`. Performance is not a concern here, so the arithmetic favours
+//! simplicity over speed.
+
+/// A field element, not necessarily in canonical form.
+type Fe = [u64; 4];
+
+/// The field modulus `2^255 - 19`.
+const P: Fe = [
+    0xffff_ffff_ffff_ffed,
+    0xffff_ffff_ffff_ffff,
+    0xffff_ffff_ffff_ffff,
+    0x7fff_ffff_ffff_ffff,
+];
+
+/// The curve constant `d = -121665 / 121666`.
+const D: Fe = [
+    0x75eb_4dca_1359_78a3,
+    0x0070_0a4d_4141_d8ab,
+    0x8cc7_4079_7779_e898,
+    0x5203_6cee_2b6f_fe73,
+];
+
+/// The exponent `(p - 1) / 2` used to compute the Legendre symbol.
+const LEGENDRE_EXPONENT: Fe = [
+    0xffff_ffff_ffff_fff6,
+    0xffff_ffff_ffff_ffff,
+    0xffff_ffff_ffff_ffff,
+    0x3fff_ffff_ffff_ffff,
+];
+
+const ONE: Fe = [1, 0, 0, 0];
+
+const ZERO: Fe = [0, 0, 0, 0];
+
+/// Checks whether the bytes represent a compressed point on the ed25519 curve.
+///
+/// This follows the point decompression performed by the runtime: the `y`
+/// coordinate is read with the sign bit cleared and the point is valid when
+/// `x^2 = (y^2 - 1) / (d * y^2 + 1)` has a solution.
+pub(crate) fn is_on_curve(bytes: &[u8; 32]) -> bool {
+    let mut y = ZERO;
+    for (limb, chunk) in y.iter_mut().zip(bytes.chunks_exact(8)) {
+        *limb = u64::from_le_bytes(chunk.try_into().unwrap());
+    }
+    // Clear the sign bit of `x`.
+    y[3] &= 0x7fff_ffff_ffff_ffff;
+
+    let yy = mul(&y, &y);
+    let u = sub(&yy, &ONE);
+    let v = add(&mul(&D, &yy), &ONE);
+
+    if canonical(&v) == ZERO {
+        return canonical(&u) == ZERO;
+    }
+
+    // `u / v` is a square iff `u * v` is a square.
+    let legendre = canonical(&pow(&mul(&u, &v), &LEGENDRE_EXPONENT));
+    legendre == ZERO || legendre == ONE
+}
+
+/// Returns `a >= b`, both interpreted as 256-bit integers.
+fn ge(a: &Fe, b: &Fe) -> bool {
+    for i in (0..4).rev() {
+        if a[i] != b[i] {
+            return a[i] > b[i];
+        }
+    }
+    true
+}
+
+/// Returns `a - b` as 256-bit integers, assuming `a >= b`.
+fn sub_raw(a: &Fe, b: &Fe) -> Fe {
+    let mut result = ZERO;
+    let mut borrow = false;
+
+    for i in 0..4 {
+        let (value, b1) = a[i].overflowing_sub(b[i]);
+        let (value, b2) = value.overflowing_sub(borrow as u64);
+        result[i] = value;
+        borrow = b1 || b2;
+    }
+
+    result
+}
+
+/// Reduces the element to its canonical form (`< p`).
+fn canonical(a: &Fe) -> Fe {
+    let mut result = *a;
+    while ge(&result, &P) {
+        result = sub_raw(&result, &P);
+    }
+    result
+}
+
+/// Adds `carry * 2^256` to the element, folding it back since `2^256 = 38 (mod p)`.
+fn fold(mut value: Fe, mut carry: u128) -> Fe {
+    while carry != 0 {
+        let mut c = carry * 38;
+        for limb in value.iter_mut() {
+            let sum = *limb as u128 + c;
+            *limb = sum as u64;
+            c = sum >> 64;
+        }
+        carry = c;
+    }
+    value
+}
+
+fn add(a: &Fe, b: &Fe) -> Fe {
+    let mut result = ZERO;
+    let mut carry = 0u128;
+
+    for i in 0..4 {
+        let sum = a[i] as u128 + b[i] as u128 + carry;
+        result[i] = sum as u64;
+        carry = sum >> 64;
+    }
+
+    fold(result, carry)
+}
+
+fn sub(a: &Fe, b: &Fe) -> Fe {
+    add(a, &sub_raw(&P, &canonical(b)))
+}
+
+fn mul(a: &Fe, b: &Fe) -> Fe {
+    let mut wide = [0u64; 8];
+
+    for i in 0..4 {
+        let mut carry = 0u128;
+        for j in 0..4 {
+            let value = a[i] as u128 * b[j] as u128 + wide[i + j] as u128 + carry;
+            wide[i + j] = value as u64;
+            carry = value >> 64;
+        }
+        wide[i + 4] = carry as u64;
+    }
+
+    // Reduce the upper 256 bits using `2^256 = 38 (mod p)`.
+    let mut result = ZERO;
+    let mut carry = 0u128;
+
+    for i in 0..4 {
+        let value = wide[i] as u128 + wide[i + 4] as u128 * 38 + carry;
+        result[i] = value as u64;
+        carry = value >> 64;
+    }
+
+    fold(result, carry)
+}
+
+fn pow(base: &Fe, exponent: &Fe) -> Fe {
+    let mut result = ONE;
+
+    for i in (0..256).rev() {
+        result = mul(&result, &result);
+        if (exponent[i / 64] >> (i % 64)) & 1 == 1 {
+            result = mul(&result, base);
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_field_arithmetic() {
+        // p - 1 = -1
+        let minus_one = sub(&ZERO, &ONE);
+        assert_eq!(canonical(&minus_one), sub_raw(&P, &ONE));
+        assert_eq!(canonical(&mul(&minus_one, &minus_one)), ONE);
+        // d * (-121666) = 121665
+        let d = mul(&D, &sub(&ZERO, &[121666, 0, 0, 0]));
+        assert_eq!(canonical(&d), [121665, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_is_on_curve() {
+        // y = 0 and y = 1 are on the curve.
+        assert!(is_on_curve(&[0; 32]));
+        let mut one = [0; 32];
+        one[0] = 1;
+        assert!(is_on_curve(&one));
+
+        // Compressed ed25519 base point.
+        let base_point = [
+            0x58, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66,
+            0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66,
+            0x66, 0x66, 0x66, 0x66,
+        ];
+        assert!(is_on_curve(&base_point));
+
+        // y = 2 is not on the curve.
+        let mut two = [0; 32];
+        two[0] = 2;
+        assert!(!is_on_curve(&two));
+    }
+}
@@ -1,6 +1,8 @@
 //! Defines the lazy program entrypoint and the context to access the
 //! input buffer.
 
+use core::mem::MaybeUninit;
+
 use crate::{
     account_info::{Account, AccountInfo, MAX_PERMITTED_DATA_INCREASE},
     program_error::ProgramError,
@@ -47,6 +49,10 @@ macro_rules! lazy_entrypoint {
 /// ) -> ProgramResult;
 /// ```
 ///
+/// The context can also be declared as `InstructionContext<N>` to keep a table of the
+/// first `N` accounts parsed, so duplicated accounts can be resolved with
+/// [`InstructionContext::next_account_resolved`].
+///
 /// # Example
 ///
 /// Defining an entrypoint and making it conditional on the `bpf-entrypoint` feature. Although
@@ -85,7 +91,9 @@ macro_rules! lazy_program_entrypoint {
         /// Program entrypoint.
         #[no_mangle]
         pub unsafe extern "C" fn entrypoint(input: *mut u8) -> u64 {
-            match $process_instruction($crate::entrypoint::lazy::InstructionContext::new(input)) {
+            match $process_instruction(
+                $crate::entrypoint::lazy::InstructionContext::with_account_table(input),
+            ) {
                 Ok(_) => $crate::SUCCESS,
                 Err(error) => error.into(),
             }
@@ -97,7 +105,11 @@ macro_rules! lazy_program_entrypoint {
 ///
 /// This is a wrapper around the input buffer that provides methods to read the accounts
 /// and instruction data. It is used by the lazy entrypoint to access the input data on demand.
-pub struct InstructionContext {
+///
+/// The context keeps a table with the first `ACCOUNTS` accounts parsed, which is used to
+/// resolve duplicated accounts. By default, the table is empty and duplicated accounts are
+/// returned as [`MaybeAccount::Duplicated`].
+pub struct InstructionContext<const ACCOUNTS: usize = 0> {
     /// Pointer to the runtime input buffer for the instruction.
     input: *mut u8,
 
@@ -108,16 +120,44 @@ pub struct InstructionContext {
 
     /// Current memory offset on the input buffer.
     offset: usize,
+
+    /// Number of accounts parsed or skipped.
+    ///
+    /// This value is only tracked when the table has capacity.
+    parsed: usize,
+
+    /// Number of accounts on the table.
+    ///
+    /// The table only contains consecutive accounts from the start of the input, so
+    /// accounts are no longer added once an account is skipped.
+    tabled: usize,
+
+    /// Accounts parsed so far, up to `ACCOUNTS`.
+    accounts: [MaybeUninit<AccountInfo>; ACCOUNTS],
 }
 
 impl InstructionContext {
     /// Creates a new [`InstructionContext`] for the input buffer.
     #[inline(always)]
     pub fn new(input: *mut u8) -> Self {
+        Self::with_account_table(input)
+    }
+}
+
+impl<const ACCOUNTS: usize> InstructionContext<ACCOUNTS> {
+    /// Creates a new [`InstructionContext`] for the input buffer, keeping a table of
+    /// the first `ACCOUNTS` accounts parsed.
+    #[inline(always)]
+    pub fn with_account_table(input: *mut u8) -> Self {
+        const UNINIT: MaybeUninit<AccountInfo> = MaybeUninit::<AccountInfo>::uninit();
+
         Self {
             input,
             remaining: unsafe { *(input as *const u64) },
             offset: core::mem::size_of::<u64>(),
+            parsed: 0,
+            tabled: 0,
+            accounts: [UNINIT; ACCOUNTS],
         }
     }
 
@@ -138,7 +178,117 @@ impl InstructionContext {
             .checked_sub(1)
             .ok_or(ProgramError::NotEnoughAccountKeys)?;
 
-        Ok(unsafe { read_account(self.input, &mut self.offset) })
+        Ok(unsafe { self.read_next_account() })
+    }
+
+    /// Reads the next account for the instruction, resolving duplicated accounts.
+    ///
+    /// Duplicated accounts are resolved using the table of accounts parsed, so the
+    /// returned [`AccountInfo`] of a duplicated account shares the memory of the
+    /// original account.
+    ///
+    /// # Error
+    ///
+    /// Returns a [`ProgramError::NotEnoughAccountKeys`] error if there are
+    /// no remaining accounts, or a [`ProgramError::InvalidArgument`] error if the
+    /// account is a duplicate of an account beyond the capacity of the table.
+    #[inline(always)]
+    pub fn next_account_resolved(&mut self) -> Result<AccountInfo, ProgramError> {
+        match self.next_account()? {
+            MaybeAccount::Account(account) => Ok(account),
+            MaybeAccount::Duplicated(index) => self
+                .account(index as usize)
+                .cloned()
+                .ok_or(ProgramError::InvalidArgument),
+        }
+    }
+
+    /// Reads the next `N` accounts for the instruction.
+    ///
+    /// # Error
+    ///
+    /// Returns a [`ProgramError::NotEnoughAccountKeys`] error if there are
+    /// less than `N` remaining accounts.
+    #[inline(always)]
+    pub fn next_accounts<const N: usize>(&mut self) -> Result<[MaybeAccount; N], ProgramError> {
+        if self.remaining < N as u64 {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+
+        self.remaining -= N as u64;
+
+        Ok(core::array::from_fn(|_| unsafe {
+            self.read_next_account()
+        }))
+    }
+
+    /// Skips the next `count` accounts for the instruction.
+    ///
+    /// Skipped accounts are not parsed &mdash; the offset is moved forward using only the
+    /// length of the account data and their borrow state is left untouched. Therefore,
+    /// skipped accounts cannot be accessed and are not added to the table of accounts.
+    ///
+    /// # Error
+    ///
+    /// Returns a [`ProgramError::NotEnoughAccountKeys`] error if there are
+    /// less than `count` remaining accounts.
+    #[inline(always)]
+    pub fn skip_accounts(&mut self, count: u64) -> Result<(), ProgramError> {
+        if self.remaining < count {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+
+        for _ in 0..count {
+            unsafe { skip_account(self.input, &mut self.offset) };
+        }
+
+        self.remaining -= count;
+
+        if ACCOUNTS > 0 {
+            self.parsed += count as usize;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the instruction data and the program id for the instruction.
+    ///
+    /// Any remaining account is skipped, as in [`Self::skip_accounts`], so the instruction
+    /// data is available regardless of the number of accounts read.
+    #[inline(always)]
+    pub fn instruction_data_fast(&mut self) -> (&[u8], &Pubkey) {
+        for _ in 0..self.remaining {
+            unsafe { skip_account(self.input, &mut self.offset) };
+        }
+
+        if ACCOUNTS > 0 {
+            self.parsed += self.remaining as usize;
+        }
+
+        self.remaining = 0;
+
+        unsafe {
+            let data_len = *(self.input.add(self.offset) as *const u64) as usize;
+            let data = self.input.add(self.offset + core::mem::size_of::<u64>());
+
+            (
+                core::slice::from_raw_parts(data, data_len),
+                &*(data.add(data_len) as *const Pubkey),
+            )
+        }
+    }
+
+    /// Returns the account at `index`, if it is on the table of accounts.
+    ///
+    /// Duplicated accounts are returned as the original account.
+    #[inline(always)]
+    pub fn account(&self, index: usize) -> Option<&AccountInfo> {
+        if index < self.tabled {
+            // SAFETY: the table entries are initialized as accounts are parsed.
+            Some(unsafe { self.accounts[index].assume_init_ref() })
+        } else {
+            None
+        }
     }
 
     /// Returns the next account for the instruction.
@@ -153,7 +303,33 @@ impl InstructionContext {
     /// there are no more remaining accounts results in undefined behavior.
     #[inline(always)]
     pub unsafe fn next_account_unchecked(&mut self) -> MaybeAccount {
-        read_account(self.input, &mut self.offset)
+        self.read_next_account()
+    }
+
+    /// Reads the next account, adding it to the table of accounts parsed.
+    #[inline(always)]
+    unsafe fn read_next_account(&mut self) -> MaybeAccount {
+        let account = read_account(self.input, &mut self.offset);
+
+        // The table is only maintained when it has capacity, so the default
+        // context does not pay for it.
+        if ACCOUNTS > 0 {
+            if self.parsed == self.tabled && self.tabled < ACCOUNTS {
+                let info = match &account {
+                    MaybeAccount::Account(account) => account.clone(),
+                    // A duplicate refers to an earlier account, which is already
+                    // on the table since no account was skipped.
+                    MaybeAccount::Duplicated(original) => {
+                        self.accounts[*original as usize].assume_init_ref().clone()
+                    }
+                };
+                self.accounts[self.tabled].write(info);
+                self.tabled += 1;
+            }
+            self.parsed += 1;
+        }
+
+        account
     }
 
     /// Returns the number of available accounts.
@@ -250,13 +426,33 @@ impl MaybeAccount {
     }
 }
 
+/// Skip an account from the input buffer, without modifying its borrow state.
+///
+/// This can only be called with a buffer that was serialized by the runtime as
+/// it assumes a specific memory layout.
+#[allow(clippy::cast_ptr_alignment, clippy::missing_safety_doc)]
+#[inline(always)]
+unsafe fn skip_account(input: *mut u8, offset: &mut usize) {
+    let account: *mut Account = input.add(*offset) as *mut _;
+
+    if (*account).borrow_state == NON_DUP_MARKER {
+        *offset += core::mem::size_of::<Account>();
+        *offset += (*account).data_len as usize;
+        *offset += MAX_PERMITTED_DATA_INCREASE;
+        *offset += (*offset as *const u8).align_offset(BPF_ALIGN_OF_U128);
+        *offset += core::mem::size_of::<u64>();
+    } else {
+        *offset += core::mem::size_of::<u64>();
+    }
+}
+
 /// Read an account from the input buffer.
 ///
 /// This can only be called with a buffer that was serialized by the runtime as
 /// it assumes a specific memory layout.
 #[allow(clippy::cast_ptr_alignment, clippy::missing_safety_doc)]
 #[inline(always)]
-unsafe fn read_account(input: *mut u8, offset: &mut usize) -> MaybeAccount {
+pub(super) unsafe fn read_account(input: *mut u8, offset: &mut usize) -> MaybeAccount {
     let account: *mut Account = input.add(*offset) as *mut _;
 
     if (*account).borrow_state == NON_DUP_MARKER {
@@ -276,3 +472,108 @@ unsafe fn read_account(input: *mut u8, offset: &mut usize) -> MaybeAccount {
         MaybeAccount::Duplicated((*account).borrow_state)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{serialize, AccountBuilder};
+
+    #[test]
+    fn test_next_account_resolved() {
+        let accounts = [
+            AccountBuilder::new([1; 32]).lamports(1).build(),
+            AccountBuilder::new([2; 32]).lamports(2).build(),
+            AccountBuilder::new([1; 32]).build(),
+            AccountBuilder::new([2; 32]).build(),
+        ];
+        let mut input = serialize(&[7; 32], &accounts, &[1, 2]);
+
+        let mut context = InstructionContext::<2>::with_account_table(input.as_mut_ptr());
+
+        let first = context.next_account_resolved().unwrap();
+        assert_eq!(first.lamports(), 1);
+        assert!(context.account(1).is_none());
+
+        // Duplicated accounts read with `next_account` are also tracked.
+        let second = context.next_account().unwrap().assume_account();
+        assert!(context.account(1) == Some(&second));
+
+        assert!(context.next_account_resolved().unwrap() == first);
+        assert!(context.next_account_resolved().unwrap() == second);
+        assert_eq!(
+            context.next_account_resolved().err(),
+            Some(ProgramError::NotEnoughAccountKeys)
+        );
+        assert_eq!(context.instruction_data().unwrap(), &[1, 2]);
+
+        // Duplicates of accounts beyond the capacity of the table cannot be resolved.
+        let mut input = serialize(&[7; 32], &accounts, &[1, 2]);
+        let mut context = InstructionContext::<1>::with_account_table(input.as_mut_ptr());
+        context.next_account_resolved().unwrap();
+        context.next_account_resolved().unwrap();
+        assert!(context.next_account_resolved().is_ok());
+        assert_eq!(
+            context.next_account_resolved().err(),
+            Some(ProgramError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn test_next_accounts() {
+        let accounts = [
+            AccountBuilder::new([1; 32]).data(&[1; 3]).build(),
+            AccountBuilder::new([2; 32]).build(),
+            AccountBuilder::new([1; 32]).build(),
+            AccountBuilder::new([3; 32]).data(&[3; 9]).build(),
+        ];
+        let mut input = serialize(&[7; 32], &accounts, &[1, 2]);
+
+        let mut context = InstructionContext::<4>::with_account_table(input.as_mut_ptr());
+
+        let [first, second] = context.next_accounts::<2>().unwrap();
+        assert_eq!(first.assume_account().key(), &[1; 32]);
+        assert_eq!(second.assume_account().key(), &[2; 32]);
+        assert_eq!(context.remaining(), 2);
+        assert!(context.next_accounts::<3>().is_err());
+
+        context.skip_accounts(1).unwrap();
+        assert!(context.account(2).is_none());
+
+        // Accounts read after skipping are not added to the table.
+        context.next_account().unwrap();
+        assert!(context.account(3).is_none());
+        assert!(context.skip_accounts(1).is_err());
+
+        let (data, program_id) = context.instruction_data_fast();
+        assert_eq!(data, &[1, 2]);
+        assert_eq!(program_id, &[7; 32]);
+    }
+
+    #[test]
+    fn test_instruction_data_fast() {
+        let accounts = [
+            AccountBuilder::new([1; 32]).data(&[1; 5]).build(),
+            AccountBuilder::new([1; 32]).build(),
+            AccountBuilder::new([2; 32]).build(),
+        ];
+        let mut input = serialize(&[7; 32], &accounts, &[4, 5, 6]);
+
+        let mut context = InstructionContext::new(input.as_mut_ptr());
+        context.next_account().unwrap();
+
+        let (data, program_id) = context.instruction_data_fast();
+        assert_eq!(data, &[4, 5, 6]);
+        assert_eq!(program_id, &[7; 32]);
+        assert_eq!(context.remaining(), 0);
+
+        // The last account has no data, so its header is followed by the realloc
+        // padding, the rent epoch, the instruction data length, the instruction data
+        // and the program id.
+        let offset = input.len()
+            - (core::mem::size_of::<Account>() + MAX_PERMITTED_DATA_INCREASE + 8 + 8 + 3 + 32);
+        // Skipped accounts keep the non-duplicated marker as their borrow state.
+        assert_eq!(input.as_slice()[offset], NON_DUP_MARKER);
+        // The account read has its borrow state reset.
+        assert_eq!(input.as_slice()[8], 0);
+    }
+}
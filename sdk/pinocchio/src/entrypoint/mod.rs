@@ -4,8 +4,9 @@
 pub mod lazy;
 pub use lazy::{InstructionContext, MaybeAccount};
 
-#[cfg(target_os = "solana")]
-pub use alloc::BumpAllocator;
+use core::marker::PhantomData;
+
+pub use alloc::{ArenaAllocator, BumpAllocator, Checkpoint, FreeListAllocator};
 
 use crate::{
     account_info::{Account, AccountInfo, MAX_PERMITTED_DATA_INCREASE},
@@ -19,6 +20,12 @@ pub const HEAP_START_ADDRESS: u64 = 0x300000000;
 /// Length of the heap memory region used for program heap.
 pub const HEAP_LENGTH: usize = 32 * 1024;
 
+/// Maximum length of the heap memory region that a transaction can request.
+///
+/// The heap length is requested using the `RequestHeapFrame` instruction of the
+/// compute budget program and must be a multiple of `1024` bytes.
+pub const MAX_HEAP_LENGTH: usize = 256 * 1024;
+
 #[deprecated(
     since = "0.6.0",
     note = "Use `ProgramResult` from the crate root instead"
@@ -57,6 +64,15 @@ pub const SUCCESS: u64 = super::SUCCESS;
 /// be ignored. When the maximum is not specified, the default is `64`. This is currently the [maximum
 /// number of accounts] that a transaction may lock in a block.
 ///
+/// An optional `error` argument specifies a type implementing
+/// [`ProgramErrorCode`](crate::program_error::ProgramErrorCode). When specified, the name and
+/// code of an error returned by the program are logged before returning, using
+/// `program_error::log_error`, which requires the `log` feature:
+///
+/// ```ignore
+/// entrypoint!(process_instruction, error = VaultError);
+/// ```
+///
 /// [maximum number of accounts]: https://github.com/anza-xyz/agave/blob/ccabfcf84921977202fd06d3197cbcea83742133/runtime/src/bank.rs#L3207-L3219
 ///
 /// # Examples
@@ -94,8 +110,11 @@ macro_rules! entrypoint {
     ( $process_instruction:ident ) => {
         entrypoint!($process_instruction, { $crate::MAX_TX_ACCOUNTS });
     };
-    ( $process_instruction:ident, $maximum:expr ) => {
-        $crate::program_entrypoint!($process_instruction, $maximum);
+    ( $process_instruction:ident, error = $error:ty ) => {
+        entrypoint!($process_instruction, { $crate::MAX_TX_ACCOUNTS }, error = $error);
+    };
+    ( $process_instruction:ident, $maximum:expr $(, error = $error:ty)? ) => {
+        $crate::program_entrypoint!($process_instruction, $maximum $(, error = $error)?);
         $crate::default_allocator!();
         $crate::default_panic_handler!();
     };
@@ -111,7 +130,10 @@ macro_rules! program_entrypoint {
     ( $process_instruction:ident ) => {
         program_entrypoint!($process_instruction, { $crate::MAX_TX_ACCOUNTS });
     };
-    ( $process_instruction:ident, $maximum:expr ) => {
+    ( $process_instruction:ident, error = $error:ty ) => {
+        program_entrypoint!($process_instruction, { $crate::MAX_TX_ACCOUNTS }, error = $error);
+    };
+    ( $process_instruction:ident, $maximum:expr $(, error = $error:ty)? ) => {
         /// Program entrypoint.
         #[no_mangle]
         pub unsafe extern "C" fn entrypoint(input: *mut u8) -> u64 {
@@ -129,6 +151,68 @@ macro_rules! program_entrypoint {
                 &program_id,
                 core::slice::from_raw_parts(accounts.as_ptr() as _, count),
                 &instruction_data,
+            ) {
+                Ok(()) => $crate::SUCCESS,
+                Err(error) => {
+                    $( $crate::program_error::log_error::<$error>(&error); )?
+                    error.into()
+                }
+            }
+        }
+    };
+}
+
+/// Declare the program entrypoint exposing the accounts beyond the maximum.
+///
+/// This macro is similar to the [`crate::program_entrypoint!`] macro, but accounts exceeding
+/// the maximum number of accounts are not silently ignored. Instead, they are provided to the
+/// program as a [`RemainingAccounts`] iterator, which parses them on-demand. This allows
+/// programs to support a variable number of "remaining" accounts without having to raise the
+/// maximum to [`crate::MAX_TX_ACCOUNTS`].
+///
+/// The first argument is the name of a function with this type signature:
+///
+/// ```ignore
+/// fn process_instruction(
+///     program_id: &Pubkey,            // Public key of the account the program was loaded into
+///     accounts: &[AccountInfo],       // Accounts up to the specified maximum
+///     remaining: RemainingAccounts,   // Accounts exceeding the maximum
+///     instruction_data: &[u8],        // Serialized instruction-specific data
+/// ) -> ProgramResult;
+/// ```
+///
+/// The second argument is the maximum number of accounts that the program is expecting. A program
+/// that does not expect any account beyond the maximum can reject the input by checking whether
+/// there are remaining accounts:
+///
+/// ```ignore
+/// if !remaining.is_empty() {
+///     return Err(ProgramError::InvalidArgument);
+/// }
+/// ```
+///
+/// Note that this macro does not set up a global allocator nor a panic handler.
+#[macro_export]
+macro_rules! program_entrypoint_with_remaining {
+    ( $process_instruction:ident, $maximum:expr ) => {
+        /// Program entrypoint.
+        #[no_mangle]
+        pub unsafe extern "C" fn entrypoint(input: *mut u8) -> u64 {
+            const UNINIT: core::mem::MaybeUninit<$crate::account_info::AccountInfo> =
+                core::mem::MaybeUninit::<$crate::account_info::AccountInfo>::uninit();
+            // Create an array of uninitialized account infos.
+            let mut accounts = [UNINIT; $maximum];
+
+            let (program_id, count, remaining, instruction_data) =
+                $crate::entrypoint::deserialize_with_remaining::<$maximum>(input, &mut accounts);
+
+            // Call the program's entrypoint passing `count` account infos; we know that
+            // they are initialized so we cast the pointer to a slice of `[AccountInfo]`.
+            match $process_instruction(
+                &program_id,
+                core::slice::from_raw_parts(accounts.as_ptr() as _, count),
+                remaining,
+                &instruction_data,
             ) {
                 Ok(()) => $crate::SUCCESS,
                 Err(error) => error.into(),
@@ -137,6 +221,63 @@ macro_rules! program_entrypoint {
     };
 }
 
+/// Iterator over the accounts exceeding the maximum number of accounts of the entrypoint.
+///
+/// Accounts are only parsed when the iterator advances. Each account is represented as
+/// a [`MaybeAccount`], since it can either be an [`AccountInfo`] or the index of a duplicated
+/// account. A duplicated index lower than the number of accounts parsed by the entrypoint
+/// refers to an account on the `accounts` slice; otherwise, it refers to an account
+/// previously returned by this iterator.
+pub struct RemainingAccounts<'a> {
+    /// Pointer to the runtime input buffer.
+    input: *mut u8,
+
+    /// Offset of the next account on the input buffer.
+    offset: usize,
+
+    /// Number of accounts still to be parsed.
+    remaining: usize,
+
+    /// The input buffer is borrowed for the lifetime of the iterator.
+    _input: PhantomData<&'a mut [u8]>,
+}
+
+impl RemainingAccounts<'_> {
+    /// Returns the number of accounts still to be parsed.
+    #[inline(always)]
+    pub fn remaining(&self) -> usize {
+        self.remaining
+    }
+
+    /// Returns `true` if there are no accounts to be parsed.
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.remaining == 0
+    }
+}
+
+impl Iterator for RemainingAccounts<'_> {
+    type Item = MaybeAccount;
+
+    #[inline(always)]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        // SAFETY: the offset points to one of the `remaining` accounts of a buffer
+        // serialized by the runtime.
+        Some(unsafe { lazy::read_account(self.input, &mut self.offset) })
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl ExactSizeIterator for RemainingAccounts<'_> {}
+
 /// Deserialize the input arguments.
 ///
 /// This can only be called from the entrypoint function of a Solana program and with
@@ -147,12 +288,38 @@ pub unsafe fn deserialize<'a, const MAX_ACCOUNTS: usize>(
     input: *mut u8,
     accounts: &mut [core::mem::MaybeUninit<AccountInfo>],
 ) -> (&'a Pubkey, usize, &'a [u8]) {
+    let (program_id, processed, _, instruction_data) =
+        deserialize_with_remaining::<MAX_ACCOUNTS>(input, accounts);
+    (program_id, processed, instruction_data)
+}
+
+/// Deserialize the input arguments, returning an iterator over the accounts
+/// exceeding `MAX_ACCOUNTS`.
+///
+/// The remaining accounts are not parsed, so their borrow state is only reset
+/// when they are returned by the iterator.
+///
+/// This can only be called from the entrypoint function of a Solana program and with
+/// a buffer that was serialized by the runtime.
+#[allow(clippy::cast_ptr_alignment, clippy::missing_safety_doc)]
+#[inline(always)]
+pub unsafe fn deserialize_with_remaining<'a, const MAX_ACCOUNTS: usize>(
+    input: *mut u8,
+    accounts: &mut [core::mem::MaybeUninit<AccountInfo>],
+) -> (&'a Pubkey, usize, RemainingAccounts<'a>, &'a [u8]) {
     let mut offset: usize = 0;
 
     // total number of accounts present; it only process up to MAX_ACCOUNTS
     let total_accounts = *(input.add(offset) as *const u64) as usize;
     offset += core::mem::size_of::<u64>();
 
+    let mut remaining = RemainingAccounts {
+        input,
+        offset,
+        remaining: 0,
+        _input: PhantomData,
+    };
+
     let processed = if total_accounts > 0 {
         // number of accounts to process (limited to MAX_ACCOUNTS)
         let processed = core::cmp::min(total_accounts, MAX_ACCOUNTS);
@@ -182,6 +349,10 @@ pub unsafe fn deserialize<'a, const MAX_ACCOUNTS: usize>(
             }
         }
 
+        // the remaining accounts start at the current offset
+        remaining.offset = offset;
+        remaining.remaining = total_accounts - processed;
+
         // process any remaining accounts to move the offset to the instruction
         // data (there is a duplication of logic but we avoid testing whether we
         // have space for the account or not)
@@ -215,7 +386,7 @@ pub unsafe fn deserialize<'a, const MAX_ACCOUNTS: usize>(
     // program id
     let program_id: &Pubkey = &*(input.add(offset) as *const Pubkey);
 
-    (program_id, processed, instruction_data)
+    (program_id, processed, remaining, instruction_data)
 }
 
 /// Default panic handler.
@@ -224,7 +395,7 @@ pub unsafe fn deserialize<'a, const MAX_ACCOUNTS: usize>(
 /// panic occurred.
 ///
 /// Note that this requires the `"std"` feature to be enabled.
-#[cfg(feature = "std")]
+#[cfg(all(feature = "std", not(feature = "minimal-panic")))]
 #[macro_export]
 macro_rules! default_panic_handler {
     () => {
@@ -238,12 +409,37 @@ macro_rules! default_panic_handler {
     };
 }
 
+/// Default panic handler.
+///
+/// This macro sets up a default panic handler that logs the file, line and column where the
+/// panic occurred and the panic message. The message is formatted into a fixed size buffer
+/// on the stack using [`crate::log::sol_log_panic`], so it does not require an allocator.
+///
+/// This is used when the `"std"` feature is disabled and the `"log"` feature is enabled.
+#[cfg(all(not(feature = "std"), not(feature = "minimal-panic"), feature = "log"))]
+#[macro_export]
+macro_rules! default_panic_handler {
+    () => {
+        /// Default panic handler.
+        #[cfg(all(not(feature = "custom-panic"), target_os = "solana"))]
+        #[no_mangle]
+        fn custom_panic(info: &core::panic::PanicInfo<'_>) {
+            // Panic reporting.
+            $crate::log::sol_log_panic(info);
+        }
+    };
+}
+
 /// Default panic handler.
 ///
 /// This macro sets up a default panic handler that logs the file where the panic occurred.
 ///
-/// This is used when the `"std"` feature is disabled.
-#[cfg(not(feature = "std"))]
+/// This is used when the `"std"` and `"log"` features are disabled.
+#[cfg(all(
+    not(feature = "std"),
+    not(feature = "minimal-panic"),
+    not(feature = "log")
+))]
 #[macro_export]
 macro_rules! default_panic_handler {
     () => {
@@ -260,18 +456,172 @@ macro_rules! default_panic_handler {
     };
 }
 
+/// Default panic handler.
+///
+/// This macro sets up a panic handler that only logs a static message, which avoids
+/// including the formatting code in the program binary.
+///
+/// This is used when the `"minimal-panic"` feature is enabled.
+#[cfg(feature = "minimal-panic")]
+#[macro_export]
+macro_rules! default_panic_handler {
+    () => {
+        /// Default panic handler.
+        #[cfg(all(not(feature = "custom-panic"), target_os = "solana"))]
+        #[no_mangle]
+        fn custom_panic(_info: &core::panic::PanicInfo<'_>) {
+            // Panic reporting.
+            $crate::log::sol_log("** PANICKED **");
+        }
+    };
+}
+
 /// Default global allocator.
 ///
 /// This macro sets up a default global allocator that uses a bump allocator to allocate memory.
+///
+/// The length of the heap can be specified with the optional `heap_size` argument; when it is
+/// not specified, the default is [`HEAP_LENGTH`]. A larger heap must be requested by the transaction
+/// using the `RequestHeapFrame` instruction of the compute budget program.
+///
+/// ```ignore
+/// default_allocator!(heap_size = 64 * 1024);
+/// ```
+///
+/// The macro also declares a `heap` module, whose `heap::length()` function returns the length
+/// of the heap configured for the allocator.
 #[macro_export]
 macro_rules! default_allocator {
     () => {
+        $crate::default_allocator!(heap_size = $crate::entrypoint::HEAP_LENGTH);
+    };
+    ( heap_size = $heap_size:expr ) => {
+        $crate::global_allocator!(BumpAllocator, $heap_size);
+    };
+}
+
+/// Arena global allocator.
+///
+/// This macro sets up a global allocator that uses an [`ArenaAllocator`] to allocate memory.
+/// Besides `heap::length()`, the `heap` module declared by the macro has functions to take a
+/// [`Checkpoint`] of the heap and later reset the allocator to it, releasing all memory
+/// allocated in between:
+///
+/// ```ignore
+/// let checkpoint = heap::checkpoint();
+/// // ...allocations only used until the reset...
+/// unsafe { heap::reset(checkpoint) };
+/// ```
+///
+/// The functions are also available on host builds, where programs use the system allocator
+/// instead: checkpoints are empty, resetting does nothing and `heap::used()` returns `0`.
+///
+/// The length of the heap can be specified with the optional `heap_size` argument; when it is
+/// not specified, the default is [`HEAP_LENGTH`].
+#[macro_export]
+macro_rules! arena_allocator {
+    () => {
+        $crate::arena_allocator!(heap_size = $crate::entrypoint::HEAP_LENGTH);
+    };
+    ( heap_size = $heap_size:expr ) => {
+        $crate::global_allocator!(
+            ArenaAllocator,
+            $heap_size,
+            /// Returns the global allocator, which is only used on-chain.
+            #[cfg(all(not(feature = "custom-heap"), target_os = "solana"))]
+            #[inline(always)]
+            fn arena() -> ::core::option::Option<&'static $crate::entrypoint::ArenaAllocator> {
+                ::core::option::Option::Some(&super::A)
+            },
+            /// Returns the global allocator, which is only used on-chain.
+            #[cfg(not(all(not(feature = "custom-heap"), target_os = "solana")))]
+            #[inline(always)]
+            fn arena() -> ::core::option::Option<&'static $crate::entrypoint::ArenaAllocator> {
+                ::core::option::Option::None
+            },
+            /// Returns a checkpoint of the current position of the global allocator.
+            #[inline(always)]
+            pub fn checkpoint() -> $crate::entrypoint::Checkpoint {
+                arena()
+                    .map(|allocator| allocator.checkpoint())
+                    .unwrap_or_default()
+            },
+            /// Resets the global allocator to the specified checkpoint, releasing all memory
+            /// allocated after the checkpoint was taken.
+            ///
+            /// # Safety
+            ///
+            /// The caller must guarantee that none of the memory allocated after the checkpoint
+            /// was taken is still in use.
+            #[inline(always)]
+            pub unsafe fn reset(checkpoint: $crate::entrypoint::Checkpoint) {
+                if let ::core::option::Option::Some(allocator) = arena() {
+                    allocator.reset(checkpoint);
+                }
+            },
+            /// Returns the number of bytes currently allocated by the global allocator.
+            #[inline(always)]
+            pub fn used() -> usize {
+                arena().map_or(0, |allocator| allocator.used())
+            }
+        );
+    };
+}
+
+/// Free list global allocator.
+///
+/// This macro sets up a global allocator that uses a [`FreeListAllocator`] to allocate memory.
+/// Memory of deallocated blocks is reused by later allocations of the same size class, which is
+/// useful for programs that allocate in loops.
+///
+/// The length of the heap can be specified with the optional `heap_size` argument; when it is
+/// not specified, the default is [`HEAP_LENGTH`].
+#[macro_export]
+macro_rules! free_list_allocator {
+    () => {
+        $crate::free_list_allocator!(heap_size = $crate::entrypoint::HEAP_LENGTH);
+    };
+    ( heap_size = $heap_size:expr ) => {
+        $crate::global_allocator!(FreeListAllocator, $heap_size);
+    };
+}
+
+/// Declares a global allocator of the specified type, together with a `heap` module
+/// containing the `length()` function and any additional `item`.
+///
+/// Use one of the [`crate::default_allocator!`], [`crate::arena_allocator!`] or
+/// [`crate::free_list_allocator!`] macros instead.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! global_allocator {
+    ( $allocator:ident, $heap_size:expr $(, $item:item)* ) => {
+        const _: () = assert!(
+            $heap_size <= $crate::entrypoint::MAX_HEAP_LENGTH && $heap_size % 1024 == 0,
+            "heap size must be a multiple of 1024 bytes up to 256KB"
+        );
+
         #[cfg(all(not(feature = "custom-heap"), target_os = "solana"))]
         #[global_allocator]
-        static A: $crate::entrypoint::BumpAllocator = $crate::entrypoint::BumpAllocator {
+        static A: $crate::entrypoint::$allocator = $crate::entrypoint::$allocator {
             start: $crate::entrypoint::HEAP_START_ADDRESS as usize,
-            len: $crate::entrypoint::HEAP_LENGTH,
+            len: $heap_size,
         };
+
+        /// Functions of the global allocator.
+        #[allow(dead_code)]
+        pub mod heap {
+            // The heap size expression is resolved in the scope of the macro invocation.
+            #[allow(unused_imports)]
+            use super::*;
+
+            /// Returns the length of the heap memory region used by the global allocator.
+            #[inline(always)]
+            pub fn length() -> usize {
+                $heap_size
+            }
+
+            $($item)*
+        }
     };
 }
 
@@ -301,12 +651,52 @@ macro_rules! no_allocator {
     };
 }
 
-#[cfg(target_os = "solana")]
 mod alloc {
-    //! The bump allocator used as the default rust heap when running programs.
+    //! The allocators used as the rust heap when running programs.
 
     extern crate alloc;
 
+    /// Number of size classes of the [`FreeListAllocator`].
+    const SIZE_CLASSES: usize = 10;
+
+    /// Size of the smallest size class of the [`FreeListAllocator`].
+    ///
+    /// Blocks need to be large enough to store the pointer to the next free block.
+    const MIN_CLASS_SIZE: usize = core::mem::size_of::<usize>();
+
+    /// Size of the largest size class of the [`FreeListAllocator`].
+    const MAX_CLASS_SIZE: usize = MIN_CLASS_SIZE << (SIZE_CLASSES - 1);
+
+    /// Allocates memory from the top of the heap, moving the current position down.
+    ///
+    /// The current position is stored at the start of the heap and a position of `0`
+    /// represents an unused heap. Allocations are not allowed to overlap the `reserved`
+    /// bytes at the start of the heap.
+    #[allow(clippy::arithmetic_side_effects)]
+    #[inline(always)]
+    unsafe fn bump(
+        start: usize,
+        len: usize,
+        reserved: usize,
+        size: usize,
+        align: usize,
+    ) -> *mut u8 {
+        let pos_ptr = start as *mut usize;
+
+        let mut pos = *pos_ptr;
+        if pos == 0 {
+            // First time, set starting position.
+            pos = start + len;
+        }
+        pos = pos.saturating_sub(size);
+        pos &= !(align.wrapping_sub(1));
+        if pos < start + reserved {
+            return core::ptr::null_mut();
+        }
+        *pos_ptr = pos;
+        pos as *mut u8
+    }
+
     /// The bump allocator used as the default rust heap when running programs.
     pub struct BumpAllocator {
         pub start: usize,
@@ -321,24 +711,272 @@ mod alloc {
         /// Allocates memory as a bump allocator.
         #[inline]
         unsafe fn alloc(&self, layout: core::alloc::Layout) -> *mut u8 {
-            let pos_ptr = self.start as *mut usize;
+            bump(
+                self.start,
+                self.len,
+                core::mem::size_of::<*mut u8>(),
+                layout.size(),
+                layout.align(),
+            )
+        }
+        #[inline]
+        unsafe fn dealloc(&self, _: *mut u8, _: core::alloc::Layout) {
+            // I'm a bump allocator, I don't free.
+        }
+    }
 
-            let mut pos = *pos_ptr;
-            if pos == 0 {
-                // First time, set starting position.
-                pos = self.start + self.len;
-            }
-            pos = pos.saturating_sub(layout.size());
-            pos &= !(layout.align().wrapping_sub(1));
-            if pos < self.start + core::mem::size_of::<*mut u8>() {
-                return core::ptr::null_mut();
+    /// Position of an [`ArenaAllocator`] that it can be reset to.
+    ///
+    /// The default checkpoint represents an unused heap.
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    pub struct Checkpoint(usize);
+
+    /// A bump allocator that can release memory by resetting to a [`Checkpoint`].
+    pub struct ArenaAllocator {
+        pub start: usize,
+        pub len: usize,
+    }
+
+    impl ArenaAllocator {
+        /// Returns a [`Checkpoint`] of the current position of the allocator.
+        #[inline(always)]
+        pub fn checkpoint(&self) -> Checkpoint {
+            // SAFETY: the start of the heap stores the current position.
+            Checkpoint(unsafe { *(self.start as *const usize) })
+        }
+
+        /// Resets the allocator to the specified [`Checkpoint`], releasing all memory
+        /// allocated after the checkpoint was taken.
+        ///
+        /// # Safety
+        ///
+        /// The caller must guarantee that none of the memory allocated after the checkpoint
+        /// was taken is still in use, and that the checkpoint was taken from this allocator.
+        #[inline(always)]
+        pub unsafe fn reset(&self, checkpoint: Checkpoint) {
+            *(self.start as *mut usize) = checkpoint.0;
+        }
+
+        /// Returns the number of bytes currently allocated.
+        #[allow(clippy::arithmetic_side_effects)]
+        #[inline(always)]
+        pub fn used(&self) -> usize {
+            match self.checkpoint().0 {
+                0 => 0,
+                pos => self.start + self.len - pos,
             }
-            *pos_ptr = pos;
-            pos as *mut u8
+        }
+    }
+
+    unsafe impl alloc::alloc::GlobalAlloc for ArenaAllocator {
+        /// Allocates memory as a bump allocator.
+        #[inline]
+        unsafe fn alloc(&self, layout: core::alloc::Layout) -> *mut u8 {
+            bump(
+                self.start,
+                self.len,
+                core::mem::size_of::<*mut u8>(),
+                layout.size(),
+                layout.align(),
+            )
         }
         #[inline]
         unsafe fn dealloc(&self, _: *mut u8, _: core::alloc::Layout) {
-            // I'm a bump allocator, I don't free.
+            // Memory is released when the allocator is reset.
+        }
+    }
+
+    /// An allocator that reuses deallocated memory.
+    ///
+    /// Allocations up to 4096 bytes are rounded up to a power of two size class and
+    /// deallocated blocks are kept on a free list per size class, to be reused by later
+    /// allocations of the same class. Larger allocations are served by bumping the heap
+    /// and are never freed.
+    ///
+    /// The heads of the free lists are stored at the start of the heap, after the
+    /// current position.
+    pub struct FreeListAllocator {
+        pub start: usize,
+        pub len: usize,
+    }
+
+    impl FreeListAllocator {
+        /// Returns the index of the size class for the layout, if the layout fits
+        /// in one of the size classes.
+        #[inline(always)]
+        fn size_class(layout: &core::alloc::Layout) -> Option<usize> {
+            let size = core::cmp::max(layout.size(), layout.align());
+
+            if size <= MAX_CLASS_SIZE {
+                let size = core::cmp::max(size, MIN_CLASS_SIZE).next_power_of_two();
+                Some((size.trailing_zeros() - MIN_CLASS_SIZE.trailing_zeros()) as usize)
+            } else {
+                None
+            }
+        }
+
+        /// Returns a pointer to the head of the free list of the size class.
+        #[inline(always)]
+        unsafe fn head(&self, class: usize) -> *mut usize {
+            (self.start as *mut usize).add(1 + class)
+        }
+    }
+
+    unsafe impl alloc::alloc::GlobalAlloc for FreeListAllocator {
+        /// Allocates memory from the free list of the size class, falling back to
+        /// bumping the heap when the list is empty.
+        #[inline]
+        unsafe fn alloc(&self, layout: core::alloc::Layout) -> *mut u8 {
+            // The position is followed by the heads of the free lists.
+            let reserved = (1 + SIZE_CLASSES) * core::mem::size_of::<usize>();
+
+            match Self::size_class(&layout) {
+                Some(class) => {
+                    let head = self.head(class);
+
+                    if *head != 0 {
+                        let block = *head as *mut usize;
+                        *head = *block;
+                        block as *mut u8
+                    } else {
+                        // Blocks are aligned to their size, which is at least the
+                        // alignment of the layout.
+                        let size = MIN_CLASS_SIZE << class;
+                        bump(self.start, self.len, reserved, size, size)
+                    }
+                }
+                None => bump(
+                    self.start,
+                    self.len,
+                    reserved,
+                    layout.size(),
+                    layout.align(),
+                ),
+            }
+        }
+
+        /// Adds the block to the free list of the size class.
+        #[inline]
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: core::alloc::Layout) {
+            if let Some(class) = Self::size_class(&layout) {
+                let head = self.head(class);
+                *(ptr as *mut usize) = *head;
+                *head = ptr as usize;
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use alloc::alloc::GlobalAlloc;
+        use core::alloc::Layout;
+
+        #[test]
+        fn test_bump_allocator() {
+            let mut heap = [0u64; 128];
+            let allocator = BumpAllocator {
+                start: heap.as_mut_ptr() as usize,
+                len: core::mem::size_of_val(&heap),
+            };
+
+            let layout = Layout::from_size_align(256, 8).unwrap();
+            let first = unsafe { allocator.alloc(layout) };
+            let second = unsafe { allocator.alloc(layout) };
+
+            assert!(!first.is_null());
+            assert_eq!(first as usize - second as usize, 256);
+            assert!(!unsafe { allocator.alloc(layout) }.is_null());
+            // The space left overlaps the position stored at the start of the heap.
+            assert!(unsafe { allocator.alloc(layout) }.is_null());
+        }
+
+        #[test]
+        fn test_arena_allocator() {
+            let mut heap = [0u64; 128];
+            let allocator = ArenaAllocator {
+                start: heap.as_mut_ptr() as usize,
+                len: core::mem::size_of_val(&heap),
+            };
+
+            let layout = Layout::from_size_align(64, 8).unwrap();
+            let first = unsafe { allocator.alloc(layout) };
+            assert_eq!(allocator.used(), 64);
+
+            let checkpoint = allocator.checkpoint();
+            let second = unsafe { allocator.alloc(layout) };
+            assert_eq!(allocator.used(), 128);
+
+            unsafe { allocator.reset(checkpoint) };
+            assert_eq!(allocator.used(), 64);
+            assert_eq!(unsafe { allocator.alloc(layout) }, second);
+            assert_ne!(first, second);
+        }
+
+        /// Heap memory aligned to the largest size class, so the blocks allocated do
+        /// not depend on the address of the heap.
+        #[repr(C, align(4096))]
+        struct AlignedHeap<const LEN: usize>([u8; LEN]);
+
+        impl<const LEN: usize> AlignedHeap<LEN> {
+            fn free_list_allocator(&mut self) -> FreeListAllocator {
+                FreeListAllocator {
+                    start: self.0.as_mut_ptr() as usize,
+                    len: LEN,
+                }
+            }
+        }
+
+        #[test]
+        fn test_free_list_allocator() {
+            let mut heap = AlignedHeap([0; 4096]);
+            let allocator = heap.free_list_allocator();
+
+            let small = Layout::from_size_align(20, 4).unwrap();
+            let first = unsafe { allocator.alloc(small) };
+            let second = unsafe { allocator.alloc(small) };
+            // Blocks are rounded up to the size class.
+            assert_eq!(first as usize - second as usize, 32);
+            assert_eq!(first as usize % 32, 0);
+
+            // Deallocated blocks are reused by allocations of the same class.
+            unsafe { allocator.dealloc(first, small) };
+            let same_class = Layout::from_size_align(32, 8).unwrap();
+            assert_eq!(unsafe { allocator.alloc(same_class) }, first);
+
+            // Allocating in a loop does not exhaust the heap.
+            let large = Layout::from_size_align(1024, 8).unwrap();
+            for _ in 0..16 {
+                let block = unsafe { allocator.alloc(large) };
+                assert!(!block.is_null());
+                unsafe { allocator.dealloc(block, large) };
+            }
+        }
+
+        #[test]
+        fn test_free_list_allocator_huge() {
+            // The heap can hold two allocations larger than the largest size class and
+            // a block of the largest size class.
+            let mut heap = AlignedHeap([0; 32 * 1024]);
+            let allocator = heap.free_list_allocator();
+
+            let huge = Layout::from_size_align(MAX_CLASS_SIZE + 1, 8).unwrap();
+            let first = unsafe { allocator.alloc(huge) };
+            assert!(!first.is_null());
+
+            // Allocations that do not fit in a size class are never freed, so the
+            // memory is not reused.
+            unsafe { allocator.dealloc(first, huge) };
+            let second = unsafe { allocator.alloc(huge) };
+            assert!(!second.is_null());
+            assert!(second < first);
+            assert!((first as usize - second as usize) >= huge.size());
+
+            // Neither is the memory reused by allocations of a size class.
+            let largest = Layout::from_size_align(MAX_CLASS_SIZE, 8).unwrap();
+            let block = unsafe { allocator.alloc(largest) };
+            assert!(!block.is_null());
+            assert!((block as usize) + largest.size() <= second as usize);
         }
     }
 }
@@ -359,3 +997,89 @@ unsafe impl core::alloc::GlobalAlloc for NoAllocator {
         // I deny all allocations, so I don't need to free.
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{serialize, AccountBuilder};
+    use core::mem::MaybeUninit;
+
+    #[test]
+    fn test_deserialize_with_remaining() {
+        let accounts = [
+            AccountBuilder::new([1; 32]).lamports(1).build(),
+            AccountBuilder::new([2; 32]).lamports(2).build(),
+            AccountBuilder::new([3; 32]).lamports(3).build(),
+            AccountBuilder::new([1; 32]).build(),
+            AccountBuilder::new([3; 32]).build(),
+        ];
+        let mut input = serialize(&[7; 32], &accounts, &[1, 2, 3]);
+        let mut infos = [const { MaybeUninit::<AccountInfo>::uninit() }; 2];
+
+        let (program_id, count, mut remaining, instruction_data) =
+            unsafe { deserialize_with_remaining::<2>(input.as_mut_ptr(), &mut infos) };
+
+        assert_eq!(program_id, &[7; 32]);
+        assert_eq!(count, 2);
+        assert_eq!(instruction_data, &[1, 2, 3]);
+        assert_eq!(remaining.len(), 3);
+
+        let third = remaining.next().unwrap().assume_account();
+        assert_eq!(third.key(), &[3; 32]);
+        assert_eq!(third.lamports(), 3);
+        // The borrow state is reset when the account is parsed.
+        assert!(third.try_borrow_mut_data().is_ok());
+
+        assert!(matches!(
+            remaining.next(),
+            Some(MaybeAccount::Duplicated(0))
+        ));
+        assert!(matches!(
+            remaining.next(),
+            Some(MaybeAccount::Duplicated(2))
+        ));
+        assert!(remaining.is_empty());
+        assert!(remaining.next().is_none());
+    }
+
+    #[allow(unexpected_cfgs)]
+    mod free_list {
+        const HEAP_SIZE: usize = 64 * 1024;
+
+        crate::free_list_allocator!(heap_size = HEAP_SIZE);
+    }
+
+    #[allow(unexpected_cfgs)]
+    mod arena {
+        crate::arena_allocator!(heap_size = 32 * 1024);
+    }
+
+    #[test]
+    fn test_heap_length() {
+        assert_eq!(free_list::heap::length(), 64 * 1024);
+        assert_eq!(arena::heap::length(), 32 * 1024);
+    }
+
+    #[test]
+    fn test_arena_heap_functions() {
+        // Host builds use the system allocator, so the functions do nothing.
+        let checkpoint = arena::heap::checkpoint();
+        assert_eq!(checkpoint, Checkpoint::default());
+        unsafe { arena::heap::reset(checkpoint) };
+        assert_eq!(arena::heap::used(), 0);
+    }
+
+    #[test]
+    fn test_deserialize_without_remaining() {
+        let accounts = [AccountBuilder::new([1; 32]).build()];
+        let mut input = serialize(&[7; 32], &accounts, &[]);
+        let mut infos = [const { MaybeUninit::<AccountInfo>::uninit() }; 2];
+
+        let (_, count, remaining, instruction_data) =
+            unsafe { deserialize_with_remaining::<2>(input.as_mut_ptr(), &mut infos) };
+
+        assert_eq!(count, 1);
+        assert!(instruction_data.is_empty());
+        assert!(remaining.is_empty());
+    }
+}
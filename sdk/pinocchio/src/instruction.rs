@@ -1,8 +1,14 @@
 //! Instruction types.
 
-use core::{marker::PhantomData, ops::Deref};
+use core::{marker::PhantomData, mem::MaybeUninit, ops::Deref};
 
-use crate::{account_info::AccountInfo, pubkey::Pubkey};
+use crate::{
+    account_info::AccountInfo,
+    program::invoke_signed_with_bounds,
+    program_error::ProgramError,
+    pubkey::{checked_create_program_address, Pubkey, MAX_SEEDS},
+    ProgramResult,
+};
 
 /// Information about a CPI instruction.
 #[repr(C)]
@@ -41,7 +47,7 @@ pub struct ProcessedSiblingInstruction {
 #[derive(Clone)]
 pub struct Account<'a> {
     // Public key of the account.
-    key: *const Pubkey,
+    pub(crate) key: *const Pubkey,
 
     // Number of lamports owned by this account.
     lamports: *const u64,
@@ -59,10 +65,10 @@ pub struct Account<'a> {
     rent_epoch: u64,
 
     // Transaction was signed by this account's key?
-    is_signer: bool,
+    pub(crate) is_signer: bool,
 
     // Is the account writable?
-    is_writable: bool,
+    pub(crate) is_writable: bool,
 
     // This account's data contains a loaded program (and is now read-only).
     executable: bool,
@@ -162,6 +168,194 @@ impl<'a> From<&'a AccountInfo> for AccountMeta<'a> {
     }
 }
 
+/// Builder of a cross-program instruction stored on the stack.
+///
+/// The builder holds up to `ACCOUNTS` accounts and `DATA` bytes of instruction
+/// data. Accounts are added together with their [`AccountInfo`], so the
+/// instruction can be invoked directly from the builder. Data fields are written
+/// in little-endian order.
+///
+/// # Example
+///
+/// ```ignore
+/// let mut builder = InstructionBuilder::<2, 12>::new(&SYSTEM_PROGRAM_ID);
+/// builder
+///     .push_account(from, true, true)?
+///     .push_account(to, true, false)?
+///     .push_u32(2)?
+///     .push_u64(lamports)?;
+///
+/// builder.invoke_signed(signers)
+/// ```
+pub struct InstructionBuilder<'a, const ACCOUNTS: usize, const DATA: usize> {
+    /// Public key of the program.
+    program_id: &'a Pubkey,
+
+    /// Metadata of the accounts.
+    account_metas: [MaybeUninit<AccountMeta<'a>>; ACCOUNTS],
+
+    /// Accounts matching the metadata.
+    account_infos: [MaybeUninit<&'a AccountInfo>; ACCOUNTS],
+
+    /// Number of accounts added.
+    accounts_len: usize,
+
+    /// Instruction data.
+    data: [MaybeUninit<u8>; DATA],
+
+    /// Length of the instruction data written.
+    data_len: usize,
+}
+
+/// Generates a method to append a numeric field to the instruction data.
+macro_rules! push_le_bytes {
+    ( $name:ident, $type:ty ) => {
+        #[doc = concat!("Appends a `", stringify!($type), "` to the instruction data.")]
+        ///
+        /// Returns [`ProgramError::InvalidInstructionData`] when the field does not
+        /// fit in the instruction data.
+        #[inline(always)]
+        pub fn $name(&mut self, value: $type) -> Result<&mut Self, ProgramError> {
+            self.push_bytes(&value.to_le_bytes())
+        }
+    };
+}
+
+impl<'a, const ACCOUNTS: usize, const DATA: usize> InstructionBuilder<'a, ACCOUNTS, DATA> {
+    /// Creates a new empty `InstructionBuilder` for the specified program.
+    pub fn new(program_id: &'a Pubkey) -> Self {
+        const UNINIT_META: MaybeUninit<AccountMeta> = MaybeUninit::<AccountMeta>::uninit();
+        const UNINIT_INFO: MaybeUninit<&AccountInfo> = MaybeUninit::<&AccountInfo>::uninit();
+        const UNINIT_BYTE: MaybeUninit<u8> = MaybeUninit::<u8>::uninit();
+
+        Self {
+            program_id,
+            account_metas: [UNINIT_META; ACCOUNTS],
+            account_infos: [UNINIT_INFO; ACCOUNTS],
+            accounts_len: 0,
+            data: [UNINIT_BYTE; DATA],
+            data_len: 0,
+        }
+    }
+
+    /// Appends an account to the instruction.
+    ///
+    /// Returns [`ProgramError::InvalidArgument`] when `ACCOUNTS` accounts were
+    /// already added.
+    pub fn push_account(
+        &mut self,
+        account_info: &'a AccountInfo,
+        is_writable: bool,
+        is_signer: bool,
+    ) -> Result<&mut Self, ProgramError> {
+        if self.accounts_len == ACCOUNTS {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        self.account_metas[self.accounts_len].write(AccountMeta::new(
+            account_info.key(),
+            is_writable,
+            is_signer,
+        ));
+        self.account_infos[self.accounts_len].write(account_info);
+        self.accounts_len += 1;
+
+        Ok(self)
+    }
+
+    /// Appends bytes to the instruction data.
+    ///
+    /// Returns [`ProgramError::InvalidInstructionData`] when the bytes do not fit
+    /// in the instruction data.
+    pub fn push_bytes(&mut self, bytes: &[u8]) -> Result<&mut Self, ProgramError> {
+        let end = self
+            .data_len
+            .checked_add(bytes.len())
+            .filter(|end| *end <= DATA)
+            .ok_or(ProgramError::InvalidInstructionData)?;
+
+        for (destination, byte) in self.data[self.data_len..end].iter_mut().zip(bytes) {
+            destination.write(*byte);
+        }
+        self.data_len = end;
+
+        Ok(self)
+    }
+
+    /// Appends a `bool` to the instruction data.
+    ///
+    /// Returns [`ProgramError::InvalidInstructionData`] when the field does not
+    /// fit in the instruction data.
+    #[inline(always)]
+    pub fn push_bool(&mut self, value: bool) -> Result<&mut Self, ProgramError> {
+        self.push_bytes(&[value as u8])
+    }
+
+    /// Appends a public key to the instruction data.
+    ///
+    /// Returns [`ProgramError::InvalidInstructionData`] when the field does not
+    /// fit in the instruction data.
+    #[inline(always)]
+    pub fn push_pubkey(&mut self, pubkey: &Pubkey) -> Result<&mut Self, ProgramError> {
+        self.push_bytes(pubkey)
+    }
+
+    push_le_bytes!(push_u8, u8);
+    push_le_bytes!(push_u16, u16);
+    push_le_bytes!(push_u32, u32);
+    push_le_bytes!(push_u64, u64);
+    push_le_bytes!(push_u128, u128);
+    push_le_bytes!(push_i8, i8);
+    push_le_bytes!(push_i16, i16);
+    push_le_bytes!(push_i32, i32);
+    push_le_bytes!(push_i64, i64);
+    push_le_bytes!(push_i128, i128);
+
+    /// Returns the metadata of the accounts added.
+    #[inline(always)]
+    pub fn account_metas(&self) -> &[AccountMeta<'a>] {
+        unsafe { core::slice::from_raw_parts(self.account_metas.as_ptr() as _, self.accounts_len) }
+    }
+
+    /// Returns the accounts added.
+    #[inline(always)]
+    pub fn account_infos(&self) -> &[&'a AccountInfo] {
+        unsafe { core::slice::from_raw_parts(self.account_infos.as_ptr() as _, self.accounts_len) }
+    }
+
+    /// Returns the instruction data written.
+    #[inline(always)]
+    pub fn data(&self) -> &[u8] {
+        unsafe { core::slice::from_raw_parts(self.data.as_ptr() as _, self.data_len) }
+    }
+
+    /// Returns the `Instruction` represented by the builder.
+    #[inline(always)]
+    pub fn instruction(&self) -> Instruction<'a, '_, 'a, '_> {
+        Instruction {
+            program_id: self.program_id,
+            data: self.data(),
+            accounts: self.account_metas(),
+        }
+    }
+
+    /// Invoke the instruction.
+    #[inline(always)]
+    pub fn invoke(&self) -> ProgramResult {
+        self.invoke_signed(&[])
+    }
+
+    /// Invoke the instruction with signatures.
+    #[inline(always)]
+    pub fn invoke_signed(&self, signers_seeds: &[Signer]) -> ProgramResult {
+        invoke_signed_with_bounds::<ACCOUNTS>(
+            &self.instruction(),
+            self.account_infos(),
+            signers_seeds,
+        )
+    }
+}
+
 /// Represents a signer seed.
 ///
 /// This struct contains the same information as a `[u8]`, but
@@ -251,6 +445,102 @@ impl<'a, 'b, const SIZE: usize> From<&'b [Seed<'a>; SIZE]> for Signer<'a, 'b> {
     }
 }
 
+/// Bump seed values referenced by a [`PdaSigner`].
+///
+/// Referencing a static value allows a `PdaSigner` to be moved without
+/// invalidating the pointer of its bump seed.
+static BUMP_SEEDS: [u8; 256] = {
+    let mut bumps = [0u8; 256];
+    let mut i = 0;
+    while i < bumps.len() {
+        bumps[i] = i as u8;
+        i += 1;
+    }
+    bumps
+};
+
+/// Owned signer seeds of a program derived address (PDA), including its bump seed.
+///
+/// `N` is the number of seeds without the bump seed. The bump seed is stored after
+/// the seeds, so the `PdaSigner` can be converted to a [`Signer`] without creating a
+/// temporary array for the bump.
+///
+/// The [`signer_seeds!`](crate::signer_seeds) macro can be used to create a `PdaSigner`.
+#[repr(C)]
+#[derive(Debug, Clone)]
+pub struct PdaSigner<'a, const N: usize> {
+    /// Signer seeds, without the bump seed.
+    seeds: [Seed<'a>; N],
+
+    /// Bump seed, which must immediately follow the seeds.
+    bump_seed: Seed<'a>,
+
+    /// Bump value.
+    bump: u8,
+}
+
+impl<'a, const N: usize> PdaSigner<'a, N> {
+    /// Creates a new `PdaSigner` from the seeds and bump of a program derived address.
+    pub fn new(seeds: [Seed<'a>; N], bump: u8) -> Self {
+        let bump_seed = Seed::from(&BUMP_SEEDS[bump as usize..bump as usize + 1]);
+
+        Self {
+            seeds,
+            bump_seed,
+            bump,
+        }
+    }
+
+    /// Returns the bump seed.
+    #[inline(always)]
+    pub fn bump(&self) -> u8 {
+        self.bump
+    }
+
+    /// Returns the [`Signer`] for the seeds, including the bump seed.
+    #[inline(always)]
+    pub fn signer(&self) -> Signer<'a, '_> {
+        Signer {
+            // The pointer is derived from the whole struct, since it is also used to
+            // read the bump seed that follows the seeds.
+            seeds: (self as *const Self).cast::<Seed<'a>>(),
+            len: N as u64 + 1,
+            _seeds: PhantomData::<&[Seed<'a>]>,
+        }
+    }
+
+    /// Returns the program derived address of the seeds.
+    ///
+    /// Returns [`ProgramError::MaxSeedLengthExceeded`] when there are too many
+    /// seeds or any of the seeds is too long.
+    pub fn address(&self, program_id: &Pubkey) -> Result<Pubkey, ProgramError> {
+        if N >= MAX_SEEDS {
+            return Err(ProgramError::MaxSeedLengthExceeded);
+        }
+
+        let mut seeds: [&[u8]; MAX_SEEDS] = [&[]; MAX_SEEDS];
+
+        for (seed, value) in seeds.iter_mut().zip(self.seeds.iter()) {
+            *seed = value;
+        }
+        seeds[N] = &self.bump_seed;
+
+        checked_create_program_address(&seeds[..N + 1], program_id)
+    }
+
+    /// Verifies that the seeds derive the `expected` address.
+    ///
+    /// Returns [`ProgramError::InvalidSeeds`] when the derived address does not
+    /// match the expected one.
+    pub fn verify(&self, program_id: &Pubkey, expected: &Pubkey) -> ProgramResult {
+        if self.address(program_id)? != *expected {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        Ok(())
+    }
+}
+
 /// Convenience macro for constructing a `Signer` from a list of seeds
 /// represented as byte slices.
 ///
@@ -271,3 +561,141 @@ macro_rules! signer {
             )*])
     };
 }
+
+/// Convenience macro for constructing a [`PdaSigner`] from a list of seeds
+/// represented as byte slices and a bump seed.
+///
+/// # Example
+///
+/// ```
+/// use pinocchio::signer_seeds;
+///
+/// let owner = [1u8; 32];
+/// let pda_bump = 255;
+/// let pda_signer = signer_seeds!(b"vault", owner.as_ref(); pda_bump);
+/// let signer = pda_signer.signer();
+/// ```
+#[macro_export]
+macro_rules! signer_seeds {
+    ( $($seed:expr),* ; $bump:expr ) => {
+        $crate::instruction::PdaSigner::new([$(
+            $crate::instruction::Seed::from($seed),
+        )*], $bump)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        program::invoke_signed,
+        pubkey::find_program_address,
+        testing::{AccountBuilder, Invocation, InvocationAccount, RecordingStubs},
+    };
+
+    #[test]
+    fn test_instruction_builder() {
+        let stubs = RecordingStubs::install();
+
+        let from = AccountBuilder::new([1; 32])
+            .signer(true)
+            .writable(true)
+            .build();
+        let to = AccountBuilder::new([2; 32]).writable(true).build();
+
+        let mut builder = InstructionBuilder::<2, 12>::new(&[0; 32]);
+        builder
+            .push_account(from.info(), true, true)
+            .unwrap()
+            .push_account(to.info(), true, false)
+            .unwrap()
+            .push_u32(2)
+            .unwrap()
+            .push_u64(1_000)
+            .unwrap();
+
+        assert_eq!(
+            builder.push_account(to.info(), false, false).err(),
+            Some(ProgramError::InvalidArgument)
+        );
+        assert_eq!(
+            builder.push_u8(0).err(),
+            Some(ProgramError::InvalidInstructionData)
+        );
+
+        builder.invoke().unwrap();
+
+        let mut data = std::vec![2, 0, 0, 0];
+        data.extend_from_slice(&1_000u64.to_le_bytes());
+
+        assert_eq!(
+            stubs.invocations(),
+            [Invocation {
+                program_id: [0; 32],
+                accounts: std::vec![
+                    InvocationAccount {
+                        pubkey: [1; 32],
+                        is_writable: true,
+                        is_signer: true,
+                    },
+                    InvocationAccount {
+                        pubkey: [2; 32],
+                        is_writable: true,
+                        is_signer: false,
+                    },
+                ],
+                data,
+                signers: std::vec![],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_pda_signer() {
+        let stubs = RecordingStubs::install();
+
+        let program_id = [9; 32];
+        let owner = [1u8; 32];
+        let (address, bump) = find_program_address(&[b"vault", &owner], &program_id);
+
+        let pda_signer = signer_seeds!(b"vault", owner.as_ref(); bump);
+        assert_eq!(pda_signer.bump(), bump);
+        assert_eq!(pda_signer.address(&program_id), Ok(address));
+        assert!(pda_signer.verify(&program_id, &address).is_ok());
+
+        let other = signer_seeds!(b"vault", owner.as_ref(); bump.wrapping_sub(1));
+        assert_eq!(
+            other.verify(&program_id, &address),
+            Err(ProgramError::InvalidSeeds)
+        );
+
+        // The signer can be moved before it is used.
+        let pda_signer = [pda_signer];
+        let pda = AccountBuilder::new(address).build();
+        let instruction = Instruction {
+            program_id: &[0; 32],
+            accounts: &[AccountMeta::readonly_signer(&address)],
+            data: &[],
+        };
+        invoke_signed(&instruction, &[pda.info()], &[pda_signer[0].signer()]).unwrap();
+
+        assert_eq!(
+            stubs.invocations()[0].signers,
+            [[b"vault".to_vec(), owner.to_vec(), std::vec![bump]]]
+        );
+    }
+
+    #[test]
+    fn test_pda_signer_seeds() {
+        // Reads the bump seed through the pointer of the signer seeds, which is
+        // checked by Miri (`cargo miri test`) to be derived from the whole signer.
+        let pda_signer = signer_seeds!(b"vault", b"owner"; 254);
+        let signer = pda_signer.signer();
+        let seeds = unsafe { core::slice::from_raw_parts(signer.seeds, signer.len as usize) };
+
+        assert_eq!(seeds.len(), 3);
+        assert_eq!(&*seeds[0], b"vault");
+        assert_eq!(&*seeds[1], b"owner");
+        assert_eq!(&*seeds[2], &[254]);
+    }
+}
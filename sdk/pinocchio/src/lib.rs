@@ -117,16 +117,34 @@
 //!   accounts.
 //! * [`next_account()`](entrypoint::InstructionContext::next_account): parses the
 //!   next available account (can be used as many times as accounts available).
+//! * [`next_account_resolved()`](entrypoint::InstructionContext::next_account_resolved):
+//!   parses the next available account, resolving duplicated accounts using the table
+//!   of accounts kept by an `InstructionContext<N>`.
+//! * [`next_accounts()`](entrypoint::InstructionContext::next_accounts): parses the
+//!   next `N` available accounts as an array.
+//! * [`skip_accounts()`](entrypoint::InstructionContext::skip_accounts): skips accounts
+//!   without parsing them.
 //! * [`instruction_data()`](entrypoint::InstructionContext::instruction_data): parses
 //!   the intruction data.
 //! * [`program_id()`](entrypoint::InstructionContext::program_id): parses the
 //!   program id.
+//! * [`instruction_data_fast()`](entrypoint::InstructionContext::instruction_data_fast):
+//!   skips any remaining account and parses both the instruction data and program id.
 //!
 //!
 //! 💡 The [`lazy_program_entrypoint!`] does not set up a global allocator nor a panic
 //! handler. A program should explicitly use one of the provided macros to set them
 //! up or include its own implementation.
 //!
+//! ### [`program_entrypoint_with_remaining!`]
+//!
+//! The [`program_entrypoint!`] ignores any account exceeding the maximum number of
+//! accounts specified. When a program accepts a variable number of accounts, the
+//! [`program_entrypoint_with_remaining!`] macro parses accounts up to the maximum and
+//! provides a [`RemainingAccounts`](entrypoint::RemainingAccounts) iterator that
+//! parses the accounts beyond the maximum on-demand. This can also be used to reject
+//! an input that has more accounts than expected.
+//!
 //! ### [`no_allocator!`]
 //!
 //! When writing programs, it can be useful to make sure the program does not attempt
@@ -163,6 +181,23 @@
 //! 💡 The [`no_allocator!`] macro can also be used in combination with the
 //! [`lazy_program_entrypoint!`].
 //!
+//! ### Heap size and alternative allocators
+//!
+//! The [`default_allocator!`] uses a heap of [`HEAP_LENGTH`](entrypoint::HEAP_LENGTH)
+//! bytes. Transactions can request a larger heap using the `RequestHeapFrame` instruction
+//! of the compute budget program, in which case the allocator should be declared with the
+//! matching size:
+//! ```ignore
+//! default_allocator!(heap_size = 64 * 1024);
+//! ```
+//!
+//! Since the default (bump) allocator never frees memory, Pinocchio also includes an
+//! [`arena_allocator!`], which can release memory by resetting to a checkpoint taken with
+//! the `heap::checkpoint()` function it declares, and a [`free_list_allocator!`], which
+//! reuses deallocated memory of the same size class. All
+//! allocator macros accept the `heap_size` argument and declare a `heap` module, whose
+//! `heap::length()` function returns the configured heap length.
+//!
 //! ## `std` crate feature
 //!
 //! By default, Pinocchio is a `no_std` crate. This means that it does not use any
@@ -180,6 +215,77 @@
 //! crate. This crate provides a lightweight `log!` macro with better compute units
 //! consumption than the standard `format!` macro without requiring the `std` library.
 //!
+//! ## `log` crate feature
+//!
+//! Pinocchio has no dependencies by default. The `log` feature adds the `pinocchio-log`
+//! crate, which is used to format messages into fixed size buffers on the stack. It
+//! enables:
+//!
+//! - a [`default_panic_handler!`] that logs the file, line and column where the panic
+//!   occurred together with the panic message, without requiring the `std` library or
+//!   an allocator; without it, only the file is logged.
+//! - the `program_error::log_error` function used by the entrypoint to log
+//!   program-specific errors.
+//! - logging a [`Pubkey`](pubkey::Pubkey) as base58 with the `log!` macro through the
+//!   `pubkey::Base58` wrapper.
+//!
+//! The `derive` and `require-log` features enable the `log` feature.
+//! ```ignore
+//! pinocchio = { version = "0.7.0", features = ["log"] }
+//! ```
+//!
+//! ## `minimal-panic` crate feature
+//!
+//! Since formatting the panic message increases the size of the program binary, the
+//! `minimal-panic` feature strips the [`default_panic_handler!`] to only log a static
+//! message:
+//! ```ignore
+//! pinocchio = { version = "0.7.0", features = ["minimal-panic"] }
+//! ```
+//!
+//! ## `derive` crate feature
+//!
+//! The `derive` feature enables the `#[derive(ProgramErrorCode)]` macro for enums of
+//! program-specific errors. The derive implements the
+//! [`ProgramErrorCode`](program_error::ProgramErrorCode) trait, the conversion into a
+//! [`ProgramError::Custom`](program_error::ProgramError::Custom) and a `TryFrom<u32>`
+//! decoder. Passing the error type to the entrypoint logs the name and code of an error
+//! returned by the program:
+//! ```ignore
+//! use pinocchio::program_error::ProgramErrorCode;
+//!
+//! #[derive(ProgramErrorCode)]
+//! pub enum VaultError {
+//!     InsufficientBalance = 100,
+//!     VaultLocked,
+//! }
+//!
+//! entrypoint!(process_instruction, error = VaultError);
+//! ```
+//!
+//! ## `require-log` crate feature
+//!
+//! The [`require!`], [`require_eq!`], [`require_keys_eq!`] and [`require_gte!`] macros
+//! return the supplied error when a condition is not met. The `require-log` feature
+//! logs the failing expression together with the file and line of the check. When the
+//! feature is disabled, the logging code is not included in the program binary:
+//! ```ignore
+//! pinocchio = { version = "0.7.0", features = ["require-log"] }
+//! ```
+//!
+//! ## `borrow-debug` crate feature
+//!
+//! [`AccountInfo::borrow_state`](account_info::AccountInfo::borrow_state) returns the
+//! number of live borrows of the lamports and data of an account. The `borrow-debug`
+//! feature also records the source location of each `try_borrow*` call, so a failed
+//! borrow logs where the conflicting borrows still alive were created. Locations are
+//! only tracked in host builds, since programs cannot have mutable static data
+//! on-chain. On-chain, a failed borrow logs only its own location, not the location
+//! of the borrow still outstanding:
+//! ```ignore
+//! pinocchio = { version = "0.7.0", features = ["borrow-debug"] }
+//! ```
+//!
 //! ## Advanced entrypoint configuration
 //!
 //! The symbols emitted by the entrypoint macros &mdash; program entrypoint, global
@@ -217,22 +323,49 @@
 //! ```ignore
 //! cargo build-sbf --features bpf-entrypoint
 //! ```
+//!
+//! ## `test-utils` crate feature
+//!
+//! The `test-utils` feature enables the `testing` module, which provides helpers
+//! to exercise programs in native `cargo test` builds. It is intended to be enabled
+//! on `dev-dependencies` only:
+//! ```ignore
+//! [dev-dependencies]
+//! pinocchio = { version = "0.7.0", features = ["test-utils"] }
+//! ```
+//!
+//! When the feature is enabled, syscalls made on non-solana targets are routed to the
+//! `SyscallStubs` installed for the current thread. By default, a `RecordingStubs`
+//! records logs, cross-program invocations and return data, so tests can assert on them.
 
 #![no_std]
 
-#[cfg(feature = "std")]
+#[cfg(any(
+    feature = "std",
+    feature = "test-utils",
+    all(feature = "borrow-debug", not(target_os = "solana")),
+    test
+))]
 extern crate std;
 
 pub mod account_info;
+#[cfg(not(target_os = "solana"))]
+mod curve;
 pub mod entrypoint;
 pub mod instruction;
 pub mod log;
 pub mod memory;
 pub mod program;
 pub mod program_error;
+#[cfg(not(target_os = "solana"))]
+#[doc(hidden)]
+pub mod program_stubs;
 pub mod pubkey;
+mod require;
 pub mod syscalls;
 pub mod sysvars;
+#[cfg(any(feature = "test-utils", test))]
+pub mod testing;
 
 #[deprecated(since = "0.7.0", note = "Use the `entrypoint` module instead")]
 pub use entrypoint::lazy as lazy_entrypoint;
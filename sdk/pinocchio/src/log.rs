@@ -33,6 +33,9 @@
 //! [`Pubkey`]: crate::pubkey::Pubkey
 //! [`pubkey::log`]: crate::pubkey::log
 
+#[cfg(feature = "log")]
+use pinocchio_log::logger::Logger;
+
 use crate::{account_info::AccountInfo, pubkey::log};
 
 /// Print a message to the log.
@@ -100,7 +103,81 @@ pub fn sol_log(message: &str) {
     }
 
     #[cfg(not(target_os = "solana"))]
-    core::hint::black_box(message);
+    crate::program_stubs::sol_log(message);
+}
+
+/// Size of the buffer used to format panic messages.
+#[cfg(feature = "log")]
+const PANIC_BUFFER: usize = 256;
+
+/// Print the location and message of a panic to the log.
+///
+/// The panic is formatted with its `Display` implementation &mdash; `panicked at
+/// <file>:<line>:<column>:` followed by the message &mdash; into a fixed size buffer
+/// on the stack, so it does not require the `std` library nor an allocator. Messages
+/// that do not fit in the buffer are truncated.
+///
+/// `PanicInfo::message` is only available since Rust 1.81, which is newer than the
+/// compiler of some versions of the Solana platform tools. The `Display` implementation
+/// is available on all of them, although older versions log the message before the
+/// location.
+#[cfg(feature = "log")]
+#[cold]
+pub fn sol_log_panic(info: &core::panic::PanicInfo<'_>) {
+    sol_log_display::<PANIC_BUFFER>(info);
+}
+
+/// Print a value formatted into a fixed size buffer on the stack.
+#[cfg(feature = "log")]
+#[inline(always)]
+fn sol_log_display<const BUFFER: usize>(value: &dyn core::fmt::Display) {
+    use core::fmt::Write;
+
+    let mut logger = Logger::<BUFFER>::default();
+    // Writing to the logger never fails, the message is truncated instead.
+    let _ = write!(logger, "{value}");
+
+    sol_log_buffer(&logger);
+}
+
+/// Size of the buffer used to format failed requirement messages.
+#[cfg(feature = "require-log")]
+const REQUIRE_BUFFER: usize = 256;
+
+/// Print the expression and location of a failed requirement to the log.
+///
+/// This is used by the `require!` family of macros when the `require-log`
+/// feature is enabled. The message is formatted as
+/// `Requirement failed: <expression> at <file>:<line>` into a fixed size
+/// buffer on the stack.
+#[cfg(feature = "require-log")]
+#[doc(hidden)]
+#[cold]
+pub fn sol_log_require(expression: &str, file: &str, line: u32) {
+    let mut logger = Logger::<REQUIRE_BUFFER>::default();
+    logger
+        .append("Requirement failed: ")
+        .append(expression)
+        .append(" at ")
+        .append(file)
+        .append(":")
+        .append(line);
+
+    sol_log_buffer(&logger);
+}
+
+/// Print the contents of a logger buffer to the log.
+///
+/// Truncation can split a multi-byte character, in which case only the
+/// valid prefix is logged.
+#[cfg(feature = "log")]
+#[inline(always)]
+pub(crate) fn sol_log_buffer(buffer: &[u8]) {
+    let message = match core::str::from_utf8(buffer) {
+        Ok(message) => message,
+        Err(error) => unsafe { core::str::from_utf8_unchecked(&buffer[..error.valid_up_to()]) },
+    };
+    sol_log(message);
 }
 
 /// Print 64-bit values represented as hexadecimal to the log.
@@ -112,7 +189,7 @@ pub fn sol_log_64(arg1: u64, arg2: u64, arg3: u64, arg4: u64, arg5: u64) {
     }
 
     #[cfg(not(target_os = "solana"))]
-    core::hint::black_box((arg1, arg2, arg3, arg4, arg5));
+    crate::program_stubs::sol_log_64(arg1, arg2, arg3, arg4, arg5);
 }
 
 /// Print some slices as base64.
@@ -123,7 +200,7 @@ pub fn sol_log_data(data: &[&[u8]]) {
     };
 
     #[cfg(not(target_os = "solana"))]
-    core::hint::black_box(data);
+    crate::program_stubs::sol_log_data(data);
 }
 
 /// Print the hexadecimal representation of a slice.
@@ -163,4 +240,52 @@ pub fn sol_log_compute_units() {
     unsafe {
         crate::syscalls::sol_log_compute_units_();
     }
+
+    #[cfg(not(target_os = "solana"))]
+    crate::program_stubs::sol_log_compute_units();
+}
+
+#[cfg(all(test, feature = "log"))]
+mod tests {
+    use core::panic::Location;
+    use std::format;
+
+    use super::*;
+    use crate::testing::RecordingStubs;
+
+    #[test]
+    fn test_sol_log_panic() {
+        let stubs = RecordingStubs::install();
+
+        // Formatted as the `Display` implementation of `PanicInfo`, which cannot be
+        // created outside of a panic.
+        let location = Location::caller();
+        sol_log_display::<PANIC_BUFFER>(&format_args!(
+            "panicked at {location}:\n{}",
+            format_args!("amount {} exceeds {}", 10, 5)
+        ));
+
+        let logs = stubs.logs();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(
+            logs[0],
+            format!(
+                "panicked at {}:{}:{}:\namount 10 exceeds 5",
+                file!(),
+                location.line(),
+                location.column()
+            )
+        );
+
+        // Long messages are truncated to the size of the buffer.
+        stubs.clear();
+        sol_log_display::<PANIC_BUFFER>(&format_args!(
+            "panicked at {location}:\n{}",
+            "x".repeat(PANIC_BUFFER)
+        ));
+
+        let logs = stubs.logs();
+        assert_eq!(logs[0].len(), PANIC_BUFFER);
+        assert!(logs[0].ends_with("xxx@"));
+    }
 }
@@ -38,7 +38,7 @@ pub unsafe fn sol_memcpy(dst: &mut [u8], src: &[u8], n: usize) {
     syscalls::sol_memcpy_(dst.as_mut_ptr(), src.as_ptr(), n as u64);
 
     #[cfg(not(target_os = "solana"))]
-    core::hint::black_box((dst, src, n));
+    crate::program_stubs::sol_memcpy(dst.as_mut_ptr(), src.as_ptr(), n);
 }
 
 /// Like C `memmove`.
@@ -66,7 +66,7 @@ pub unsafe fn sol_memmove(dst: *mut u8, src: *mut u8, n: usize) {
     syscalls::sol_memmove_(dst, src, n as u64);
 
     #[cfg(not(target_os = "solana"))]
-    core::hint::black_box((dst, src, n));
+    crate::program_stubs::sol_memmove(dst, src, n);
 }
 
 /// Like C `memcmp`.
@@ -93,14 +93,15 @@ pub unsafe fn sol_memmove(dst: *mut u8, src: *mut u8, n: usize) {
 /// likely introduce undefined behavior.
 #[inline]
 pub unsafe fn sol_memcmp(s1: &[u8], s2: &[u8], n: usize) -> i32 {
-    #[allow(unused_mut)]
-    let mut result = 0;
-
     #[cfg(target_os = "solana")]
-    syscalls::sol_memcmp_(s1.as_ptr(), s2.as_ptr(), n as u64, &mut result as *mut i32);
+    let result = {
+        let mut result = 0;
+        syscalls::sol_memcmp_(s1.as_ptr(), s2.as_ptr(), n as u64, &mut result as *mut i32);
+        result
+    };
 
     #[cfg(not(target_os = "solana"))]
-    core::hint::black_box((s1, s2, n, result));
+    let result = crate::program_stubs::sol_memcmp(s1.as_ptr(), s2.as_ptr(), n);
 
     result
 }
@@ -133,5 +134,5 @@ pub unsafe fn sol_memset(s: &mut [u8], c: u8, n: usize) {
     syscalls::sol_memset_(s.as_mut_ptr(), c, n as u64);
 
     #[cfg(not(target_os = "solana"))]
-    core::hint::black_box((s, c, n));
+    crate::program_stubs::sol_memset(s.as_mut_ptr(), c, n);
 }
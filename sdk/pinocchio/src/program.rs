@@ -1,6 +1,9 @@
 //! Cross-program invocation helpers.
 
-use core::{mem::MaybeUninit, ops::Deref};
+use core::{
+    mem::{size_of, MaybeUninit},
+    ops::Deref,
+};
 
 use crate::{
     account_info::AccountInfo,
@@ -122,6 +125,146 @@ pub fn invoke_signed<const ACCOUNTS: usize>(
     Ok(())
 }
 
+/// Invoke a cross-program instruction from a slice of `AccountInfo`s with a
+/// maximum length.
+///
+/// This is equivalent to `slice_invoke`, but the accounts are copied into an array
+/// of `MAX_ACCOUNTS` elements on the stack, so it does not require the `std` feature.
+///
+/// # Important
+///
+/// The accounts on the `account_infos` slice must be in the same order as the
+/// `accounts` field of the `instruction`.
+#[inline(always)]
+pub fn invoke_with_bounds<const MAX_ACCOUNTS: usize>(
+    instruction: &Instruction,
+    account_infos: &[&AccountInfo],
+) -> ProgramResult {
+    invoke_signed_with_bounds::<MAX_ACCOUNTS>(instruction, account_infos, &[])
+}
+
+/// Invoke a cross-program instruction with signatures from a slice of
+/// `AccountInfo`s with a maximum length.
+///
+/// This is equivalent to `slice_invoke_signed`, but the accounts are copied into an
+/// array of `MAX_ACCOUNTS` elements on the stack, so it does not require the `std`
+/// feature. When the `account_infos` slice has more than `MAX_ACCOUNTS` accounts,
+/// [`ProgramError::InvalidArgument`] is returned.
+///
+/// # Important
+///
+/// The accounts on the `account_infos` slice must be in the same order as the
+/// `accounts` field of the `instruction`.
+pub fn invoke_signed_with_bounds<const MAX_ACCOUNTS: usize>(
+    instruction: &Instruction,
+    account_infos: &[&AccountInfo],
+    signers_seeds: &[Signer],
+) -> ProgramResult {
+    if account_infos.len() > MAX_ACCOUNTS {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if instruction.accounts.len() < account_infos.len() {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    const UNINIT: MaybeUninit<Account> = MaybeUninit::<Account>::uninit();
+    let mut accounts = [UNINIT; MAX_ACCOUNTS];
+
+    for ((account, account_info), account_meta) in accounts
+        .iter_mut()
+        .zip(account_infos.iter())
+        .zip(instruction.accounts.iter())
+    {
+        if account_info.key() != account_meta.pubkey {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if account_meta.is_writable {
+            account_info.check_borrow_mut_data()?;
+            account_info.check_borrow_mut_lamports()?;
+        } else {
+            account_info.check_borrow_data()?;
+            account_info.check_borrow_lamports()?;
+        }
+
+        account.write(Account::from(*account_info));
+    }
+
+    unsafe {
+        invoke_signed_unchecked(
+            instruction,
+            core::slice::from_raw_parts(accounts.as_ptr() as _, account_infos.len()),
+            signers_seeds,
+        );
+    }
+
+    Ok(())
+}
+
+/// Invoke a cross-program instruction, looking up the `AccountInfo` of each
+/// account of the instruction by its key.
+///
+/// The `account_infos` slice can contain the accounts in any order and can be a
+/// superset of the accounts of the `instruction`, so the same slice can be used
+/// for several invocations. The instruction can have at most `ACCOUNTS` accounts.
+#[inline(always)]
+pub fn invoke_with_lookup<const ACCOUNTS: usize>(
+    instruction: &Instruction,
+    account_infos: &[&AccountInfo],
+) -> ProgramResult {
+    invoke_signed_with_lookup::<ACCOUNTS>(instruction, account_infos, &[])
+}
+
+/// Invoke a cross-program instruction with signatures, looking up the
+/// `AccountInfo` of each account of the instruction by its key.
+///
+/// The `account_infos` slice can contain the accounts in any order and can be a
+/// superset of the accounts of the `instruction`, so the same slice can be used
+/// for several invocations. The instruction can have at most `ACCOUNTS` accounts,
+/// otherwise [`ProgramError::InvalidArgument`] is returned; when the key of an
+/// account of the instruction is not found on `account_infos`,
+/// [`ProgramError::NotEnoughAccountKeys`] is returned.
+pub fn invoke_signed_with_lookup<const ACCOUNTS: usize>(
+    instruction: &Instruction,
+    account_infos: &[&AccountInfo],
+    signers_seeds: &[Signer],
+) -> ProgramResult {
+    if instruction.accounts.len() > ACCOUNTS {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    const UNINIT: MaybeUninit<Account> = MaybeUninit::<Account>::uninit();
+    let mut accounts = [UNINIT; ACCOUNTS];
+
+    for (account, account_meta) in accounts.iter_mut().zip(instruction.accounts.iter()) {
+        let account_info = account_infos
+            .iter()
+            .find(|account_info| account_info.key() == account_meta.pubkey)
+            .ok_or(ProgramError::NotEnoughAccountKeys)?;
+
+        if account_meta.is_writable {
+            account_info.check_borrow_mut_data()?;
+            account_info.check_borrow_mut_lamports()?;
+        } else {
+            account_info.check_borrow_data()?;
+            account_info.check_borrow_lamports()?;
+        }
+
+        account.write(Account::from(*account_info));
+    }
+
+    unsafe {
+        invoke_signed_unchecked(
+            instruction,
+            core::slice::from_raw_parts(accounts.as_ptr() as _, instruction.accounts.len()),
+            signers_seeds,
+        );
+    }
+
+    Ok(())
+}
+
 /// Invoke a cross-program instruction with signatures from a slice of
 /// `AccountInfo`s.
 ///
@@ -164,6 +307,181 @@ pub fn slice_invoke_signed(
     Ok(())
 }
 
+/// Accounts of a cross-program instruction that can reference the same account
+/// more than once.
+///
+/// The runtime merges the privileges of accounts that appear multiple times in
+/// an instruction &mdash; an account is a signer or writable if any of its
+/// occurrences is. `InstructionAccounts` applies the same merge when accounts are
+/// added, so every [`AccountMeta`] of an account has the same privileges, and keeps
+/// a single [`AccountInfo`] for each unique account. Borrow checks are performed
+/// once for each unique account using its merged privileges.
+///
+/// At most `MAX_ACCOUNTS` accounts, including duplicates, can be added.
+///
+/// # Examples
+///
+/// ```ignore
+/// let mut accounts = InstructionAccounts::<2>::new();
+/// accounts.push(from, true, true)?;
+/// accounts.push(to, true, false)?;
+///
+/// // `from` and `to` can be the same account.
+/// accounts.invoke(&SYSTEM_PROGRAM_ID, &data)?;
+/// ```
+pub struct InstructionAccounts<'a, const MAX_ACCOUNTS: usize> {
+    /// Metadata of the accounts, including duplicates.
+    account_metas: [MaybeUninit<AccountMeta<'a>>; MAX_ACCOUNTS],
+
+    /// Number of account metas added.
+    metas_len: usize,
+
+    /// Unique accounts and the index of their first account meta.
+    account_infos: [MaybeUninit<(&'a AccountInfo, usize)>; MAX_ACCOUNTS],
+
+    /// Number of unique accounts added.
+    infos_len: usize,
+}
+
+impl<'a, const MAX_ACCOUNTS: usize> InstructionAccounts<'a, MAX_ACCOUNTS> {
+    /// Creates a new empty `InstructionAccounts`.
+    pub fn new() -> Self {
+        const UNINIT_META: MaybeUninit<AccountMeta> = MaybeUninit::<AccountMeta>::uninit();
+        const UNINIT_INFO: MaybeUninit<(&AccountInfo, usize)> =
+            MaybeUninit::<(&AccountInfo, usize)>::uninit();
+
+        Self {
+            account_metas: [UNINIT_META; MAX_ACCOUNTS],
+            metas_len: 0,
+            account_infos: [UNINIT_INFO; MAX_ACCOUNTS],
+            infos_len: 0,
+        }
+    }
+
+    /// Adds an account to the instruction.
+    ///
+    /// When the account was already added, the privileges of all its occurrences are
+    /// merged. Returns [`ProgramError::InvalidArgument`] when `MAX_ACCOUNTS` accounts
+    /// were already added.
+    pub fn push(
+        &mut self,
+        account_info: &'a AccountInfo,
+        is_writable: bool,
+        is_signer: bool,
+    ) -> Result<(), ProgramError> {
+        if self.metas_len == MAX_ACCOUNTS {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let mut account_meta = AccountMeta::new(account_info.key(), is_writable, is_signer);
+        let mut duplicated = false;
+
+        for existing in self.metas_mut() {
+            if existing.pubkey == account_meta.pubkey {
+                duplicated = true;
+                account_meta.is_writable |= existing.is_writable;
+                account_meta.is_signer |= existing.is_signer;
+            }
+        }
+
+        if duplicated {
+            for existing in self.metas_mut() {
+                if existing.pubkey == account_meta.pubkey {
+                    existing.is_writable = account_meta.is_writable;
+                    existing.is_signer = account_meta.is_signer;
+                }
+            }
+        } else {
+            self.account_infos[self.infos_len].write((account_info, self.metas_len));
+            self.infos_len += 1;
+        }
+
+        self.account_metas[self.metas_len].write(account_meta);
+        self.metas_len += 1;
+
+        Ok(())
+    }
+
+    /// Returns the metadata of the accounts, including duplicates, with their
+    /// privileges merged.
+    #[inline(always)]
+    pub fn account_metas(&self) -> &[AccountMeta<'a>] {
+        unsafe { core::slice::from_raw_parts(self.account_metas.as_ptr() as _, self.metas_len) }
+    }
+
+    /// Returns the number of unique accounts.
+    #[inline(always)]
+    pub fn unique_len(&self) -> usize {
+        self.infos_len
+    }
+
+    /// Invoke a cross-program instruction with the accounts.
+    #[inline(always)]
+    pub fn invoke(&self, program_id: &Pubkey, data: &[u8]) -> ProgramResult {
+        self.invoke_signed(program_id, data, &[])
+    }
+
+    /// Invoke a cross-program instruction with signatures with the accounts.
+    pub fn invoke_signed(
+        &self,
+        program_id: &Pubkey,
+        data: &[u8],
+        signers_seeds: &[Signer],
+    ) -> ProgramResult {
+        let account_metas = self.account_metas();
+
+        const UNINIT: MaybeUninit<Account> = MaybeUninit::<Account>::uninit();
+        let mut accounts = [UNINIT; MAX_ACCOUNTS];
+
+        for (account, entry) in accounts
+            .iter_mut()
+            .zip(self.account_infos[..self.infos_len].iter())
+        {
+            let (account_info, index) = unsafe { entry.assume_init() };
+
+            if account_metas[index].is_writable {
+                account_info.check_borrow_mut_data()?;
+                account_info.check_borrow_mut_lamports()?;
+            } else {
+                account_info.check_borrow_data()?;
+                account_info.check_borrow_lamports()?;
+            }
+
+            account.write(Account::from(account_info));
+        }
+
+        let instruction = Instruction {
+            program_id,
+            data,
+            accounts: account_metas,
+        };
+
+        unsafe {
+            invoke_signed_unchecked(
+                &instruction,
+                core::slice::from_raw_parts(accounts.as_ptr() as _, self.infos_len),
+                signers_seeds,
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Returns the mutable metadata of the accounts added.
+    #[inline(always)]
+    fn metas_mut(&mut self) -> &mut [AccountMeta<'a>] {
+        unsafe {
+            core::slice::from_raw_parts_mut(self.account_metas.as_mut_ptr() as _, self.metas_len)
+        }
+    }
+}
+
+impl<const MAX_ACCOUNTS: usize> Default for InstructionAccounts<'_, MAX_ACCOUNTS> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Invoke a cross-program instruction but don't enforce Rust's aliasing rules.
 ///
 /// This function does not check that [`Account`]s are properly borrowable.
@@ -212,7 +530,49 @@ pub unsafe fn invoke_signed_unchecked(
     }
 
     #[cfg(not(target_os = "solana"))]
-    core::hint::black_box((instruction, accounts, signers_seeds));
+    crate::program_stubs::sol_invoke_signed(instruction, accounts, signers_seeds);
+}
+
+/// Invoke a cross-program instruction and read the value returned by the
+/// invoked program.
+///
+/// # Important
+///
+/// The accounts on the `account_infos` slice must be in the same order as the
+/// `accounts` field of the `instruction`.
+#[inline(always)]
+pub fn invoke_and_get_return<T: ReturnValue, const ACCOUNTS: usize>(
+    instruction: &Instruction,
+    account_infos: &[&AccountInfo; ACCOUNTS],
+) -> Result<T, ProgramError> {
+    invoke_signed_and_get_return(instruction, account_infos, &[])
+}
+
+/// Invoke a cross-program instruction with signatures and read the value
+/// returned by the invoked program.
+///
+/// Returns [`ProgramError::IncorrectProgramId`] when the return data was not set
+/// by the program of the `instruction` and [`ProgramError::InvalidAccountData`]
+/// when there is no return data or its length does not match the size of `T`.
+///
+/// # Important
+///
+/// The accounts on the `account_infos` slice must be in the same order as the
+/// `accounts` field of the `instruction`.
+pub fn invoke_signed_and_get_return<T: ReturnValue, const ACCOUNTS: usize>(
+    instruction: &Instruction,
+    account_infos: &[&AccountInfo; ACCOUNTS],
+    signers_seeds: &[Signer],
+) -> Result<T, ProgramError> {
+    invoke_signed(instruction, account_infos, signers_seeds)?;
+
+    let return_data = get_return_data().ok_or(ProgramError::InvalidAccountData)?;
+
+    if return_data.program_id() != instruction.program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    return_data.value().ok_or(ProgramError::InvalidAccountData)
 }
 
 /// Maximum size that can be set using [`set_return_data`].
@@ -232,7 +592,16 @@ pub fn set_return_data(data: &[u8]) {
     };
 
     #[cfg(not(target_os = "solana"))]
-    core::hint::black_box(data);
+    crate::program_stubs::sol_set_return_data(data);
+}
+
+/// Set the running program's return data to the bytes of a value.
+///
+/// The value is read back by the caller with [`ReturnData::value`] or
+/// [`invoke_and_get_return`].
+#[inline(always)]
+pub fn set_return_value<T: ReturnValue>(value: &T) {
+    set_return_data(value.as_bytes());
 }
 
 /// Get the return data from an invoked program.
@@ -265,33 +634,37 @@ pub fn set_return_data(data: &[u8]) {
 ///
 /// [rdp]: https://docs.solanalabs.com/proposals/return-data
 pub fn get_return_data() -> Option<ReturnData> {
-    #[cfg(target_os = "solana")]
-    {
-        const UNINIT_BYTE: core::mem::MaybeUninit<u8> = core::mem::MaybeUninit::<u8>::uninit();
-        let mut data = [UNINIT_BYTE; MAX_RETURN_DATA];
-        let mut program_id = Pubkey::default();
-
-        let size = unsafe {
-            crate::syscalls::sol_get_return_data(
-                data.as_mut_ptr() as *mut u8,
-                data.len() as u64,
-                &mut program_id,
-            )
-        };
+    const UNINIT_BYTE: core::mem::MaybeUninit<u8> = core::mem::MaybeUninit::<u8>::uninit();
+    let mut data = [UNINIT_BYTE; MAX_RETURN_DATA];
+    let mut program_id = Pubkey::default();
 
-        if size == 0 {
-            None
-        } else {
-            Some(ReturnData {
-                program_id,
-                data,
-                size: core::cmp::min(size as usize, MAX_RETURN_DATA),
-            })
-        }
-    }
+    #[cfg(target_os = "solana")]
+    let size = unsafe {
+        crate::syscalls::sol_get_return_data(
+            data.as_mut_ptr() as *mut u8,
+            data.len() as u64,
+            &mut program_id,
+        )
+    };
 
     #[cfg(not(target_os = "solana"))]
-    core::hint::black_box(None)
+    let size = unsafe {
+        crate::program_stubs::sol_get_return_data(
+            data.as_mut_ptr() as *mut u8,
+            data.len() as u64,
+            &mut program_id,
+        )
+    };
+
+    if size == 0 {
+        None
+    } else {
+        Some(ReturnData {
+            program_id,
+            data,
+            size: core::cmp::min(size as usize, MAX_RETURN_DATA),
+        })
+    }
 }
 
 /// Struct to hold the return data from an invoked program.
@@ -316,6 +689,14 @@ impl ReturnData {
     pub fn as_slice(&self) -> &[u8] {
         unsafe { core::slice::from_raw_parts(self.data.as_ptr() as _, self.size) }
     }
+
+    /// Return the data set by the program as a value of type `T`.
+    ///
+    /// Returns `None` when the length of the data does not match the size of `T`.
+    #[inline(always)]
+    pub fn value<T: ReturnValue>(&self) -> Option<T> {
+        T::from_bytes(self.as_slice())
+    }
 }
 
 impl Deref for ReturnData {
@@ -325,3 +706,283 @@ impl Deref for ReturnData {
         self.as_slice()
     }
 }
+
+/// Types that can be used as return data without a serialization step.
+///
+/// The return data is the in-memory representation of the value, which is read
+/// back by copying the bytes of the return data into a value of the type.
+///
+/// # Safety
+///
+/// Implementors must not have padding bytes and every bit pattern must be a valid
+/// value of the type.
+pub unsafe trait ReturnValue: Copy {
+    /// Returns the bytes of the value.
+    #[inline(always)]
+    fn as_bytes(&self) -> &[u8] {
+        unsafe { core::slice::from_raw_parts(self as *const Self as *const u8, size_of::<Self>()) }
+    }
+
+    /// Reads a value from its bytes.
+    ///
+    /// Returns `None` when the length of `bytes` does not match the size of the type.
+    #[inline(always)]
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() == size_of::<Self>() {
+            Some(unsafe { core::ptr::read_unaligned(bytes.as_ptr() as *const Self) })
+        } else {
+            None
+        }
+    }
+}
+
+macro_rules! impl_return_value {
+    ( $($type:ty),* ) => {
+        $(
+            unsafe impl ReturnValue for $type {}
+        )*
+    };
+}
+
+impl_return_value!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+unsafe impl<T: ReturnValue, const N: usize> ReturnValue for [T; N] {}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc, vec::Vec};
+
+    use super::*;
+    use crate::testing::{set_syscall_stubs, AccountBuilder, RecordingStubs, SyscallStubs};
+
+    /// Stubs that record the keys of the accounts passed to each invocation.
+    #[derive(Default)]
+    struct AccountKeysStubs {
+        keys: RefCell<Vec<Vec<Pubkey>>>,
+    }
+
+    impl SyscallStubs for AccountKeysStubs {
+        fn sol_invoke_signed(
+            &self,
+            _instruction: &Instruction,
+            accounts: &[Account],
+            _signers: &[Signer],
+        ) {
+            self.keys.borrow_mut().push(
+                accounts
+                    .iter()
+                    .map(|account| unsafe { *account.key })
+                    .collect(),
+            );
+        }
+    }
+
+    fn install() -> Rc<AccountKeysStubs> {
+        let stubs = Rc::new(AccountKeysStubs::default());
+        set_syscall_stubs(stubs.clone());
+        stubs
+    }
+
+    #[test]
+    fn test_invoke_with_bounds() {
+        let stubs = install();
+
+        let a = AccountBuilder::new([1; 32]).writable(true).build();
+        let b = AccountBuilder::new([2; 32]).build();
+
+        let metas = [
+            AccountMeta::writable(&[1; 32]),
+            AccountMeta::readonly(&[2; 32]),
+        ];
+        let instruction = Instruction {
+            program_id: &[0; 32],
+            accounts: &metas,
+            data: &[],
+        };
+        invoke_with_bounds::<4>(&instruction, &[a.info(), b.info()]).unwrap();
+
+        assert_eq!(*stubs.keys.borrow(), [std::vec![[1; 32], [2; 32]]]);
+
+        // More accounts than the bound.
+        assert_eq!(
+            invoke_with_bounds::<1>(&instruction, &[a.info(), b.info()]),
+            Err(ProgramError::InvalidArgument)
+        );
+
+        // Accounts out of order.
+        assert_eq!(
+            invoke_with_bounds::<4>(&instruction, &[b.info(), a.info()]),
+            Err(ProgramError::InvalidArgument)
+        );
+
+        // More accounts than the instruction.
+        assert_eq!(
+            invoke_with_bounds::<4>(&instruction, &[a.info(), b.info(), a.info()]),
+            Err(ProgramError::NotEnoughAccountKeys)
+        );
+    }
+
+    #[test]
+    fn test_invoke_with_lookup() {
+        let stubs = install();
+
+        let a = AccountBuilder::new([1; 32]).writable(true).build();
+        let b = AccountBuilder::new([2; 32]).build();
+        let c = AccountBuilder::new([3; 32]).signer(true).build();
+        let account_infos = [c.info(), a.info(), b.info()];
+
+        let metas = [
+            AccountMeta::writable(&[1; 32]),
+            AccountMeta::readonly(&[2; 32]),
+        ];
+        let instruction = Instruction {
+            program_id: &[0; 32],
+            accounts: &metas,
+            data: &[],
+        };
+        invoke_with_lookup::<2>(&instruction, &account_infos).unwrap();
+
+        // The same accounts can be reused for a different instruction.
+        let metas = [AccountMeta::readonly_signer(&[3; 32])];
+        let instruction = Instruction {
+            program_id: &[0; 32],
+            accounts: &metas,
+            data: &[],
+        };
+        invoke_with_lookup::<4>(&instruction, &account_infos).unwrap();
+
+        assert_eq!(
+            *stubs.keys.borrow(),
+            [std::vec![[1; 32], [2; 32]], std::vec![[3; 32]]]
+        );
+    }
+
+    #[test]
+    fn test_instruction_accounts() {
+        let stubs = install();
+
+        let a = AccountBuilder::new([1; 32])
+            .signer(true)
+            .writable(true)
+            .build();
+        let b = AccountBuilder::new([2; 32]).writable(true).build();
+
+        // Self-transfer: the same account is used twice.
+        let mut accounts = InstructionAccounts::<3>::new();
+        accounts.push(a.info(), true, true).unwrap();
+        accounts.push(a.info(), false, false).unwrap();
+        accounts.push(b.info(), false, false).unwrap();
+
+        assert_eq!(
+            accounts.push(b.info(), true, false),
+            Err(ProgramError::InvalidArgument)
+        );
+        assert_eq!(accounts.unique_len(), 2);
+
+        let flags = accounts
+            .account_metas()
+            .iter()
+            .map(|meta| (*meta.pubkey, meta.is_writable, meta.is_signer))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            flags,
+            [
+                ([1; 32], true, true),
+                ([1; 32], true, true),
+                ([2; 32], false, false)
+            ]
+        );
+
+        // Borrow checks use the merged privileges.
+        let data = a.info().try_borrow_data().unwrap();
+        assert_eq!(
+            accounts.invoke(&[0; 32], &[]),
+            Err(ProgramError::AccountBorrowFailed)
+        );
+        drop(data);
+
+        // Read-only accounts can be borrowed.
+        let data = b.info().try_borrow_data().unwrap();
+        accounts.invoke(&[0; 32], &[]).unwrap();
+        drop(data);
+
+        assert_eq!(*stubs.keys.borrow(), [std::vec![[1; 32], [2; 32]]]);
+    }
+
+    #[test]
+    fn test_invoke_with_lookup_errors() {
+        let _stubs = install();
+
+        let a = AccountBuilder::new([1; 32]).writable(true).build();
+        let b = AccountBuilder::new([2; 32]).build();
+
+        let metas = [
+            AccountMeta::writable(&[1; 32]),
+            AccountMeta::readonly(&[2; 32]),
+        ];
+        let instruction = Instruction {
+            program_id: &[0; 32],
+            accounts: &metas,
+            data: &[],
+        };
+
+        // Missing account.
+        assert_eq!(
+            invoke_with_lookup::<2>(&instruction, &[a.info()]),
+            Err(ProgramError::NotEnoughAccountKeys)
+        );
+
+        // Too many accounts for the bound.
+        assert_eq!(
+            invoke_with_lookup::<1>(&instruction, &[a.info(), b.info()]),
+            Err(ProgramError::InvalidArgument)
+        );
+
+        // Writable account already borrowed.
+        let data = a.info().try_borrow_data().unwrap();
+        assert_eq!(
+            invoke_with_lookup::<2>(&instruction, &[b.info(), a.info()]),
+            Err(ProgramError::AccountBorrowFailed)
+        );
+        drop(data);
+
+        assert!(invoke_with_lookup::<2>(&instruction, &[b.info(), a.info()]).is_ok());
+    }
+
+    #[test]
+    fn test_invoke_and_get_return() {
+        let stubs = RecordingStubs::install();
+
+        let a = AccountBuilder::new([1; 32]).build();
+        let metas = [AccountMeta::readonly(&[1; 32])];
+        let instruction = Instruction {
+            program_id: &[7; 32],
+            accounts: &metas,
+            data: &[],
+        };
+
+        assert_eq!(
+            invoke_and_get_return::<u64, 1>(&instruction, &[a.info()]),
+            Err(ProgramError::InvalidAccountData)
+        );
+
+        // Return data set by a different program.
+        stubs.set_program_id([8; 32]);
+        set_return_value(&100u64);
+        assert_eq!(
+            invoke_and_get_return::<u64, 1>(&instruction, &[a.info()]),
+            Err(ProgramError::IncorrectProgramId)
+        );
+
+        stubs.set_program_id([7; 32]);
+        set_return_value(&100u64);
+        assert_eq!(
+            invoke_and_get_return::<u64, 1>(&instruction, &[a.info()]),
+            Ok(100)
+        );
+        assert_eq!(
+            invoke_and_get_return::<u32, 1>(&instruction, &[a.info()]),
+            Err(ProgramError::InvalidAccountData)
+        );
+    }
+}
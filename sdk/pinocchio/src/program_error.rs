@@ -9,6 +9,15 @@
 //!
 //! - Not deriving `thiserror::Error` for now, as it's not clear if it's needed.
 
+#[cfg(feature = "log")]
+use pinocchio_log::logger::Logger;
+
+#[cfg(feature = "log")]
+use crate::log::sol_log_buffer;
+
+#[cfg(feature = "derive")]
+pub use pinocchio_derive::ProgramErrorCode;
+
 /// Reasons the program may fail.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum ProgramError {
@@ -95,6 +104,49 @@ pub enum ProgramError {
     IncorrectAuthority,
 }
 
+/// Program-specific errors represented by a [`ProgramError::Custom`] error code.
+///
+/// The trait can be implemented for an enum of errors using the `#[derive(ProgramErrorCode)]`
+/// macro, available with the `derive` feature. The derive also generates the conversion into
+/// a [`ProgramError`] and a `TryFrom<u32>` implementation to decode error codes.
+pub trait ProgramErrorCode: Sized {
+    /// Returns the error code.
+    fn code(&self) -> u32;
+
+    /// Returns the name of the error.
+    fn name(&self) -> &'static str;
+
+    /// Returns the error represented by the error code, if there is one.
+    fn from_code(code: u32) -> Option<Self>;
+}
+
+/// Size of the buffer used to format error messages.
+#[cfg(feature = "log")]
+const ERROR_BUFFER: usize = 128;
+
+/// Logs the name and code of a program-specific error.
+///
+/// The message is only logged when the error is a [`ProgramError::Custom`] error
+/// with a code that represents an error of type `E`.
+///
+/// This requires the `log` feature, which is also enabled by the `derive` feature.
+#[cfg(feature = "log")]
+#[cold]
+pub fn log_error<E: ProgramErrorCode>(error: &ProgramError) {
+    if let ProgramError::Custom(code) = error {
+        if let Some(error) = E::from_code(*code) {
+            let mut logger = Logger::<ERROR_BUFFER>::default();
+            logger
+                .append("Error: ")
+                .append(error.name())
+                .append(" (")
+                .append(*code)
+                .append(")");
+            sol_log_buffer(&logger);
+        }
+    }
+}
+
 /// Builtin return values occupy the upper 32 bits
 const BUILTIN_BIT_SHIFT: usize = 32;
 macro_rules! to_builtin {
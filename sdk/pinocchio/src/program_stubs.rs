@@ -0,0 +1,192 @@
+//! Host implementation of the syscalls.
+//!
+//! When the `test-utils` feature is enabled, syscalls are forwarded to the
+//! `SyscallStubs` of the current thread; otherwise, they do nothing.
+
+#[cfg(any(feature = "test-utils", test))]
+use crate::testing::syscall_stubs;
+use crate::{
+    instruction::{Account, Instruction, Signer},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+pub fn sol_log(message: &str) {
+    #[cfg(any(feature = "test-utils", test))]
+    syscall_stubs().sol_log(message);
+
+    #[cfg(not(any(feature = "test-utils", test)))]
+    core::hint::black_box(message);
+}
+
+pub fn sol_log_64(arg1: u64, arg2: u64, arg3: u64, arg4: u64, arg5: u64) {
+    #[cfg(any(feature = "test-utils", test))]
+    syscall_stubs().sol_log_64(arg1, arg2, arg3, arg4, arg5);
+
+    #[cfg(not(any(feature = "test-utils", test)))]
+    core::hint::black_box((arg1, arg2, arg3, arg4, arg5));
+}
+
+pub fn sol_log_pubkey(pubkey: &Pubkey) {
+    #[cfg(any(feature = "test-utils", test))]
+    syscall_stubs().sol_log_pubkey(pubkey);
+
+    #[cfg(not(any(feature = "test-utils", test)))]
+    core::hint::black_box(pubkey);
+}
+
+pub fn sol_log_data(data: &[&[u8]]) {
+    #[cfg(any(feature = "test-utils", test))]
+    syscall_stubs().sol_log_data(data);
+
+    #[cfg(not(any(feature = "test-utils", test)))]
+    core::hint::black_box(data);
+}
+
+pub fn sol_log_compute_units() {
+    #[cfg(any(feature = "test-utils", test))]
+    syscall_stubs().sol_log_compute_units();
+}
+
+pub fn sol_invoke_signed(
+    instruction: &Instruction,
+    accounts: &[Account],
+    signers_seeds: &[Signer],
+) {
+    #[cfg(any(feature = "test-utils", test))]
+    syscall_stubs().sol_invoke_signed(instruction, accounts, signers_seeds);
+
+    #[cfg(not(any(feature = "test-utils", test)))]
+    core::hint::black_box((instruction, accounts, signers_seeds));
+}
+
+pub fn sol_set_return_data(data: &[u8]) {
+    #[cfg(any(feature = "test-utils", test))]
+    syscall_stubs().sol_set_return_data(data);
+
+    #[cfg(not(any(feature = "test-utils", test)))]
+    core::hint::black_box(data);
+}
+
+/// Copies the return data to `data` and returns its full length, as the syscall does.
+///
+/// # Safety
+///
+/// `data` must be valid for writes of `length` bytes.
+pub unsafe fn sol_get_return_data(data: *mut u8, length: u64, program_id: &mut Pubkey) -> u64 {
+    #[cfg(any(feature = "test-utils", test))]
+    {
+        match syscall_stubs().sol_get_return_data() {
+            Some((id, return_data)) => {
+                let size = core::cmp::min(return_data.len(), length as usize);
+                core::ptr::copy_nonoverlapping(return_data.as_ptr(), data, size);
+                *program_id = id;
+                return_data.len() as u64
+            }
+            None => 0,
+        }
+    }
+
+    #[cfg(not(any(feature = "test-utils", test)))]
+    {
+        core::hint::black_box((data, length, program_id));
+        0
+    }
+}
+
+pub fn sol_create_program_address(
+    seeds: &[&[u8]],
+    program_id: &Pubkey,
+) -> Result<Pubkey, ProgramError> {
+    #[cfg(any(feature = "test-utils", test))]
+    return syscall_stubs().sol_create_program_address(seeds, program_id);
+
+    #[cfg(not(any(feature = "test-utils", test)))]
+    {
+        core::hint::black_box((seeds, program_id));
+        panic!("create_program_address is only available on target `solana`")
+    }
+}
+
+pub fn sol_try_find_program_address(seeds: &[&[u8]], program_id: &Pubkey) -> Option<(Pubkey, u8)> {
+    #[cfg(any(feature = "test-utils", test))]
+    return syscall_stubs().sol_try_find_program_address(seeds, program_id);
+
+    #[cfg(not(any(feature = "test-utils", test)))]
+    {
+        core::hint::black_box((seeds, program_id));
+        None
+    }
+}
+
+macro_rules! define_sysvar_stub {
+    ($syscall_name:ident) => {
+        /// # Safety
+        ///
+        /// `var_addr` must be valid for writes of the sysvar type.
+        pub unsafe fn $syscall_name(var_addr: *mut u8) -> u64 {
+            #[cfg(any(feature = "test-utils", test))]
+            return syscall_stubs().$syscall_name(var_addr);
+
+            #[cfg(not(any(feature = "test-utils", test)))]
+            {
+                core::hint::black_box(var_addr);
+                crate::program_error::UNSUPPORTED_SYSVAR
+            }
+        }
+    };
+}
+
+define_sysvar_stub!(sol_get_clock_sysvar);
+define_sysvar_stub!(sol_get_epoch_schedule_sysvar);
+define_sysvar_stub!(sol_get_fees_sysvar);
+define_sysvar_stub!(sol_get_rent_sysvar);
+define_sysvar_stub!(sol_get_last_restart_slot);
+define_sysvar_stub!(sol_get_epoch_rewards_sysvar);
+
+/// # Safety
+///
+/// The same safety rules apply as in [`core::ptr::copy_nonoverlapping`].
+pub unsafe fn sol_memcpy(dst: *mut u8, src: *const u8, n: usize) {
+    #[cfg(any(feature = "test-utils", test))]
+    syscall_stubs().sol_memcpy(dst, src, n);
+
+    #[cfg(not(any(feature = "test-utils", test)))]
+    core::hint::black_box((dst, src, n));
+}
+
+/// # Safety
+///
+/// The same safety rules apply as in [`core::ptr::copy`].
+pub unsafe fn sol_memmove(dst: *mut u8, src: *const u8, n: usize) {
+    #[cfg(any(feature = "test-utils", test))]
+    syscall_stubs().sol_memmove(dst, src, n);
+
+    #[cfg(not(any(feature = "test-utils", test)))]
+    core::hint::black_box((dst, src, n));
+}
+
+/// # Safety
+///
+/// `s1` and `s2` must be valid for reads of `n` bytes.
+pub unsafe fn sol_memcmp(s1: *const u8, s2: *const u8, n: usize) -> i32 {
+    #[cfg(any(feature = "test-utils", test))]
+    return syscall_stubs().sol_memcmp(s1, s2, n);
+
+    #[cfg(not(any(feature = "test-utils", test)))]
+    {
+        core::hint::black_box((s1, s2, n));
+        0
+    }
+}
+
+/// # Safety
+///
+/// The same safety rules apply as in [`core::ptr::write_bytes`].
+pub unsafe fn sol_memset(s: *mut u8, c: u8, n: usize) {
+    #[cfg(any(feature = "test-utils", test))]
+    syscall_stubs().sol_memset(s, c, n);
+
+    #[cfg(not(any(feature = "test-utils", test)))]
+    core::hint::black_box((s, c, n));
+}
@@ -1,5 +1,11 @@
 //! Public key type and functions.
 
+#[cfg(feature = "log")]
+use core::mem::MaybeUninit;
+
+#[cfg(feature = "log")]
+use pinocchio_log::logger::{Argument, Log};
+
 use crate::program_error::ProgramError;
 
 /// Number of bytes in a pubkey.
@@ -16,6 +22,28 @@ pub const MAX_SEEDS: usize = 16;
 /// [account]: https://solana.com/docs/core/accounts
 pub type Pubkey = [u8; PUBKEY_BYTES];
 
+/// Maximum length of the base58 representation of a `Pubkey`.
+pub const MAX_BASE58_LEN: usize = 44;
+
+/// Identifier of the edwards curve for the `sol_curve_validate_point` syscall.
+#[cfg(target_os = "solana")]
+const CURVE25519_EDWARDS: u64 = 0;
+
+/// Alphabet used by the base58 encoding.
+const BASE58_ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// Value of each ASCII character in the base58 alphabet, or `u8::MAX` when the
+/// character is not part of the alphabet.
+const BASE58_VALUES: [u8; 128] = {
+    let mut values = [u8::MAX; 128];
+    let mut i = 0;
+    while i < BASE58_ALPHABET.len() {
+        values[BASE58_ALPHABET[i] as usize] = i as u8;
+        i += 1;
+    }
+    values
+};
+
 /// Log a `Pubkey` from a program.
 #[inline(always)]
 pub fn log(pubkey: &Pubkey) {
@@ -25,7 +53,156 @@ pub fn log(pubkey: &Pubkey) {
     };
 
     #[cfg(not(target_os = "solana"))]
-    core::hint::black_box(pubkey);
+    crate::program_stubs::sol_log_pubkey(pubkey);
+}
+
+/// Check whether a `Pubkey` is a point on the ed25519 curve.
+///
+/// Keys of wallets are on the curve, while program derived addresses are not,
+/// so this can be used to reject or require a program derived address.
+#[inline]
+pub fn is_on_curve(pubkey: &Pubkey) -> bool {
+    #[cfg(target_os = "solana")]
+    {
+        let mut result = 0u8;
+        let error = unsafe {
+            crate::syscalls::sol_curve_validate_point(
+                CURVE25519_EDWARDS,
+                pubkey as *const _ as *const u8,
+                &mut result,
+            )
+        };
+        error == crate::SUCCESS
+    }
+
+    #[cfg(not(target_os = "solana"))]
+    crate::curve::is_on_curve(pubkey)
+}
+
+/// Encode a `Pubkey` as base58 into the `output` buffer.
+///
+/// Returns the encoded string, which uses up to [`MAX_BASE58_LEN`] bytes of the buffer.
+pub fn encode_base58<'a>(pubkey: &Pubkey, output: &'a mut [u8; MAX_BASE58_LEN]) -> &'a str {
+    // Each leading zero byte is encoded as a leading '1'.
+    let zeros = pubkey.iter().take_while(|byte| **byte == 0).count();
+
+    // Base58 digits of the value, least significant first.
+    let mut digits = [0u8; MAX_BASE58_LEN];
+    let mut length = 0;
+
+    for byte in &pubkey[zeros..] {
+        let mut carry = *byte as u32;
+
+        for digit in digits[..length].iter_mut() {
+            carry += (*digit as u32) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+
+        while carry > 0 {
+            digits[length] = (carry % 58) as u8;
+            length += 1;
+            carry /= 58;
+        }
+    }
+
+    let end = zeros + length;
+    output[..zeros].fill(b'1');
+
+    for (character, digit) in output[zeros..end]
+        .iter_mut()
+        .zip(digits[..length].iter().rev())
+    {
+        *character = BASE58_ALPHABET[*digit as usize];
+    }
+
+    // SAFETY: The output only contains characters from the base58 alphabet.
+    unsafe { core::str::from_utf8_unchecked(&output[..end]) }
+}
+
+/// Decode a `Pubkey` from its base58 representation.
+///
+/// Returns [`ProgramError::InvalidArgument`] when the string contains characters
+/// outside of the base58 alphabet or does not represent exactly 32 bytes.
+pub fn decode_base58(encoded: &str) -> Result<Pubkey, ProgramError> {
+    let encoded = encoded.as_bytes();
+
+    if encoded.len() > MAX_BASE58_LEN {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Each leading '1' is decoded as a leading zero byte.
+    let zeros = encoded
+        .iter()
+        .take_while(|character| **character == b'1')
+        .count();
+
+    // Bytes of the value, least significant first.
+    let mut bytes = [0u8; PUBKEY_BYTES];
+    let mut length = 0;
+
+    for character in &encoded[zeros..] {
+        let mut carry = match BASE58_VALUES.get(*character as usize) {
+            Some(value) if *value != u8::MAX => *value as u32,
+            _ => return Err(ProgramError::InvalidArgument),
+        };
+
+        for byte in bytes[..length].iter_mut() {
+            carry += (*byte as u32) * 58;
+            *byte = carry as u8;
+            carry >>= 8;
+        }
+
+        while carry > 0 {
+            if length == PUBKEY_BYTES {
+                return Err(ProgramError::InvalidArgument);
+            }
+            bytes[length] = carry as u8;
+            length += 1;
+            carry >>= 8;
+        }
+    }
+
+    if zeros + length != PUBKEY_BYTES {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let mut pubkey = [0u8; PUBKEY_BYTES];
+
+    for (destination, byte) in pubkey[zeros..].iter_mut().zip(bytes[..length].iter().rev()) {
+        *destination = *byte;
+    }
+
+    Ok(pubkey)
+}
+
+/// A `Pubkey` logged as its base58 representation.
+///
+/// A `Pubkey` is a type alias of `[u8; 32]`, so `log!("{}", pubkey)` uses the
+/// `pinocchio-log` implementation for arrays and prints the value of each byte.
+/// That implementation is generic over the element type and length, so a
+/// `Pubkey`-specific implementation would conflict with it; wrapping the key in
+/// `Base58` prints the base58 representation instead:
+///
+/// ```ignore
+/// use pinocchio::pubkey::Base58;
+/// use pinocchio_log::log;
+///
+/// log!("owner {}", Base58(account.owner()));
+/// ```
+///
+/// This type is only available with the `log` feature, which enables the
+/// optional `pinocchio-log` dependency.
+#[cfg(feature = "log")]
+pub struct Base58<'a>(pub &'a Pubkey);
+
+#[cfg(feature = "log")]
+impl Log for Base58<'_> {
+    #[inline]
+    fn write_with_args(&self, buffer: &mut [MaybeUninit<u8>], args: &[Argument]) -> usize {
+        let mut output = [0u8; MAX_BASE58_LEN];
+        encode_base58(self.0, &mut output).write_with_args(buffer, args)
+    }
 }
 
 /// Find a valid [program derived address][pda] and its corresponding bump seed.
@@ -140,10 +317,7 @@ pub fn try_find_program_address(seeds: &[&[u8]], program_id: &Pubkey) -> Option<
     }
 
     #[cfg(not(target_os = "solana"))]
-    {
-        core::hint::black_box((seeds, program_id));
-        None
-    }
+    crate::program_stubs::sol_try_find_program_address(seeds, program_id)
 }
 
 /// Create a valid [program derived address][pda] without searching for a bump seed.
@@ -194,10 +368,7 @@ pub fn create_program_address(
     }
 
     #[cfg(not(target_os = "solana"))]
-    {
-        core::hint::black_box((seeds, program_id));
-        panic!("create_program_address is only available on target `solana`")
-    }
+    crate::program_stubs::sol_create_program_address(seeds, program_id)
 }
 
 /// Create a valid [program derived address][pda] without searching for a bump seed.
@@ -233,3 +404,88 @@ pub fn checked_create_program_address(
 
     create_program_address(seeds, program_id)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Token program id.
+    const TOKEN_PROGRAM_ID: Pubkey = [
+        6, 221, 246, 225, 215, 101, 161, 147, 217, 203, 225, 70, 206, 235, 121, 172, 28, 180, 133,
+        237, 95, 91, 55, 145, 58, 140, 245, 133, 126, 255, 0, 169,
+    ];
+
+    #[test]
+    fn test_is_on_curve() {
+        // Compressed ed25519 base point.
+        let mut base_point = [0x66; 32];
+        base_point[0] = 0x58;
+        assert!(is_on_curve(&base_point));
+
+        // Program derived addresses are not on the curve.
+        let (address, _) = find_program_address(&[b"seed"], &TOKEN_PROGRAM_ID);
+        assert!(!is_on_curve(&address));
+    }
+
+    #[test]
+    fn test_encode_base58() {
+        let mut output = [0; MAX_BASE58_LEN];
+
+        assert_eq!(
+            encode_base58(&TOKEN_PROGRAM_ID, &mut output),
+            "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"
+        );
+        assert_eq!(
+            encode_base58(&[0; 32], &mut output),
+            "11111111111111111111111111111111"
+        );
+        assert_eq!(encode_base58(&[255; 32], &mut output).len(), MAX_BASE58_LEN);
+    }
+
+    #[test]
+    fn test_decode_base58() {
+        assert_eq!(
+            decode_base58("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"),
+            Ok(TOKEN_PROGRAM_ID)
+        );
+        assert_eq!(
+            decode_base58("11111111111111111111111111111111"),
+            Ok([0; 32])
+        );
+
+        let mut output = [0; MAX_BASE58_LEN];
+        let mut pubkey = [0; 32];
+        pubkey[1..].fill(7);
+        assert_eq!(
+            decode_base58(encode_base58(&pubkey, &mut output)),
+            Ok(pubkey)
+        );
+
+        // Invalid character.
+        assert_eq!(
+            decode_base58("0okenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"),
+            Err(ProgramError::InvalidArgument)
+        );
+        // Too short.
+        assert_eq!(decode_base58("1111"), Err(ProgramError::InvalidArgument));
+        // Too long.
+        assert_eq!(
+            decode_base58("zzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzz"),
+            Err(ProgramError::InvalidArgument)
+        );
+    }
+
+    #[cfg(feature = "log")]
+    #[test]
+    fn test_log_base58() {
+        use pinocchio_log::logger::Logger;
+
+        let mut logger = Logger::<64>::default();
+        logger.append("owner ").append(Base58(&TOKEN_PROGRAM_ID));
+
+        assert_eq!(
+            &*logger,
+            b"owner TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"
+        );
+    }
+}
@@ -0,0 +1,213 @@
+//! Macros to check conditions and return an error when they are not met.
+//!
+//! Each macro returns early from the enclosing function with the supplied
+//! error, converted using [`Into`], when its condition does not hold. When the
+//! `require-log` feature is enabled, the failing expression together with the
+//! file and line where the check is located are logged before returning. With
+//! the feature disabled, no logging code is included in the program binary.
+
+/// Returns the supplied error if the condition is `false`.
+///
+/// # Examples
+///
+/// ```
+/// use pinocchio::{program_error::ProgramError, require, ProgramResult};
+///
+/// fn check(amount: u64) -> ProgramResult {
+///     require!(amount > 0, ProgramError::InvalidArgument);
+///     Ok(())
+/// }
+///
+/// assert_eq!(check(0), Err(ProgramError::InvalidArgument));
+/// ```
+#[macro_export]
+macro_rules! require {
+    ( $condition:expr, $error:expr $(,)? ) => {
+        if !($condition) {
+            $crate::__require_log!(stringify!($condition));
+            return Err($error.into());
+        }
+    };
+}
+
+/// Returns the supplied error if the two values are not equal.
+///
+/// # Examples
+///
+/// ```
+/// use pinocchio::{program_error::ProgramError, require_eq, ProgramResult};
+///
+/// fn check(data: &[u8]) -> ProgramResult {
+///     require_eq!(data.len(), 8, ProgramError::InvalidInstructionData);
+///     Ok(())
+/// }
+///
+/// assert_eq!(check(&[0; 4]), Err(ProgramError::InvalidInstructionData));
+/// ```
+#[macro_export]
+macro_rules! require_eq {
+    ( $left:expr, $right:expr, $error:expr $(,)? ) => {
+        match (&$left, &$right) {
+            (left, right) => {
+                if *left != *right {
+                    $crate::__require_log!(concat!(stringify!($left), " == ", stringify!($right)));
+                    return Err($error.into());
+                }
+            }
+        }
+    };
+}
+
+/// Returns the supplied error if the two public keys are not equal.
+///
+/// Both arguments can be either a [`Pubkey`](crate::pubkey::Pubkey) or a
+/// reference to one.
+///
+/// # Examples
+///
+/// ```
+/// use pinocchio::{
+///     account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey,
+///     require_keys_eq, ProgramResult,
+/// };
+///
+/// fn check(account: &AccountInfo, program_id: &Pubkey) -> ProgramResult {
+///     require_keys_eq!(account.owner(), program_id, ProgramError::IllegalOwner);
+///     Ok(())
+/// }
+/// ```
+#[macro_export]
+macro_rules! require_keys_eq {
+    ( $left:expr, $right:expr, $error:expr $(,)? ) => {{
+        let (left, right): (&$crate::pubkey::Pubkey, &$crate::pubkey::Pubkey) = (&$left, &$right);
+        if left != right {
+            $crate::__require_log!(concat!(stringify!($left), " == ", stringify!($right)));
+            return Err($error.into());
+        }
+    }};
+}
+
+/// Returns the supplied error if the first value is less than the second.
+///
+/// # Examples
+///
+/// ```
+/// use pinocchio::{program_error::ProgramError, require_gte, ProgramResult};
+///
+/// fn check(balance: u64, amount: u64) -> ProgramResult {
+///     require_gte!(balance, amount, ProgramError::InsufficientFunds);
+///     Ok(())
+/// }
+///
+/// assert_eq!(check(10, 20), Err(ProgramError::InsufficientFunds));
+/// ```
+#[macro_export]
+macro_rules! require_gte {
+    ( $left:expr, $right:expr, $error:expr $(,)? ) => {
+        match (&$left, &$right) {
+            (left, right) => {
+                if *left < *right {
+                    $crate::__require_log!(concat!(stringify!($left), " >= ", stringify!($right)));
+                    return Err($error.into());
+                }
+            }
+        }
+    };
+}
+
+/// Logs the expression and location of a failed requirement.
+#[cfg(feature = "require-log")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __require_log {
+    ( $expression:expr ) => {
+        $crate::log::sol_log_require($expression, file!(), line!())
+    };
+}
+
+/// Logging of failed requirements is disabled.
+#[cfg(not(feature = "require-log"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __require_log {
+    ( $expression:expr ) => {};
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        program_error::ProgramError, pubkey::Pubkey, testing::RecordingStubs, ProgramResult,
+    };
+
+    fn check(amount: u64, balance: u64, data: &[u8], key: &Pubkey) -> ProgramResult {
+        require!(amount > 0, ProgramError::InvalidArgument);
+        require_gte!(balance, amount, ProgramError::InsufficientFunds);
+        require_eq!(data.len(), 2, ProgramError::InvalidInstructionData);
+        require_keys_eq!(key, [1; 32], ProgramError::IncorrectProgramId);
+        Ok(())
+    }
+
+    #[test]
+    fn test_require() {
+        let stubs = RecordingStubs::install();
+        let key = [1; 32];
+
+        assert_eq!(check(1, 1, &[0; 2], &key), Ok(()));
+        assert!(stubs.logs().is_empty());
+
+        assert_eq!(
+            check(0, 1, &[0; 2], &key),
+            Err(ProgramError::InvalidArgument)
+        );
+        assert_eq!(
+            check(2, 1, &[0; 2], &key),
+            Err(ProgramError::InsufficientFunds)
+        );
+        assert_eq!(
+            check(1, 1, &[0; 3], &key),
+            Err(ProgramError::InvalidInstructionData)
+        );
+        assert_eq!(
+            check(1, 1, &[0; 2], &[2; 32]),
+            Err(ProgramError::IncorrectProgramId)
+        );
+
+        #[cfg(not(feature = "require-log"))]
+        assert!(stubs.logs().is_empty());
+
+        #[cfg(feature = "require-log")]
+        {
+            let logs = stubs.logs();
+            let expressions = [
+                "amount > 0",
+                "balance >= amount",
+                "data.len() == 2",
+                "key == [1; 32]",
+            ];
+            assert_eq!(logs.len(), expressions.len());
+
+            for (log, expression) in logs.iter().zip(expressions) {
+                let prefix = std::format!("Requirement failed: {expression} at ");
+                assert!(log.starts_with(&prefix));
+                assert!(log.contains("src/require.rs:"));
+            }
+        }
+    }
+
+    #[test]
+    fn test_require_keys_eq_expression() {
+        // the macro expands to a single expression, so it can be used as a match arm
+        fn check(key: Option<&Pubkey>) -> ProgramResult {
+            match key {
+                Some(key) => require_keys_eq!(key, [1; 32], ProgramError::IncorrectProgramId),
+                None => return Err(ProgramError::NotEnoughAccountKeys),
+            }
+            Ok(())
+        }
+
+        let _stubs = RecordingStubs::install();
+
+        assert_eq!(check(Some(&[1; 32])), Ok(()));
+        assert_eq!(check(Some(&[2; 32])), Err(ProgramError::IncorrectProgramId));
+    }
+}
@@ -33,7 +33,7 @@ macro_rules! impl_sysvar_get {
             let result = unsafe { $crate::syscalls::$syscall_name(var_addr) };
 
             #[cfg(not(target_os = "solana"))]
-            let result = core::hint::black_box(var_addr as *const _ as u64);
+            let result = unsafe { $crate::program_stubs::$syscall_name(var_addr) };
 
             match result {
                 $crate::SUCCESS => Ok(var),
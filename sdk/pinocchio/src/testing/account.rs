@@ -0,0 +1,387 @@
+//! Owned accounts with the same memory layout as the ones created by the runtime.
+
+use core::{mem::size_of, ops::Deref};
+use std::vec::Vec;
+
+use crate::{
+    account_info::{Account, AccountInfo, MAX_PERMITTED_DATA_INCREASE},
+    pubkey::Pubkey,
+};
+
+/// Builder for a [`TestAccount`].
+///
+/// All fields default to zero (or `false`), so only the relevant values for a
+/// test need to be specified.
+///
+/// # Example
+///
+/// ```
+/// use pinocchio::testing::AccountBuilder;
+///
+/// let account = AccountBuilder::new([1; 32])
+///     .owner([2; 32])
+///     .lamports(1_000_000)
+///     .data(&[0; 10])
+///     .writable(true)
+///     .build();
+///
+/// assert_eq!(account.data_len(), 10);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct AccountBuilder {
+    /// Public key of the account.
+    key: Pubkey,
+
+    /// Program that owns the account.
+    owner: Pubkey,
+
+    /// The lamports in the account.
+    lamports: u64,
+
+    /// Data of the account.
+    data: Vec<u8>,
+
+    /// Indicates whether the account is a signer.
+    is_signer: bool,
+
+    /// Indicates whether the account is writable.
+    is_writable: bool,
+
+    /// Indicates whether the account represents a program.
+    executable: bool,
+}
+
+impl AccountBuilder {
+    /// Creates a new builder for an account with the given key.
+    pub fn new(key: Pubkey) -> Self {
+        Self {
+            key,
+            ..Default::default()
+        }
+    }
+
+    /// Sets the owner of the account.
+    pub fn owner(mut self, owner: Pubkey) -> Self {
+        self.owner = owner;
+        self
+    }
+
+    /// Sets the lamports of the account.
+    pub fn lamports(mut self, lamports: u64) -> Self {
+        self.lamports = lamports;
+        self
+    }
+
+    /// Sets the data of the account.
+    pub fn data(mut self, data: &[u8]) -> Self {
+        self.data = data.to_vec();
+        self
+    }
+
+    /// Sets the data of the account to `space` zeroed bytes.
+    pub fn space(mut self, space: usize) -> Self {
+        self.data = std::vec![0; space];
+        self
+    }
+
+    /// Sets whether the account is a signer.
+    pub fn signer(mut self, is_signer: bool) -> Self {
+        self.is_signer = is_signer;
+        self
+    }
+
+    /// Sets whether the account is writable.
+    pub fn writable(mut self, is_writable: bool) -> Self {
+        self.is_writable = is_writable;
+        self
+    }
+
+    /// Sets whether the account represents a program.
+    pub fn executable(mut self, executable: bool) -> Self {
+        self.executable = executable;
+        self
+    }
+
+    /// Creates the [`TestAccount`].
+    pub fn build(self) -> TestAccount {
+        TestAccount::new(
+            self.key,
+            self.owner,
+            self.lamports,
+            &self.data,
+            self.is_signer,
+            self.is_writable,
+            self.executable,
+        )
+    }
+}
+
+/// An owned account laid out in memory as the runtime does.
+///
+/// The memory of the account contains the account header, followed by the account
+/// data and [`MAX_PERMITTED_DATA_INCREASE`] bytes of padding, so the account data
+/// can be reallocated. The account dereferences to an [`AccountInfo`], which can
+/// be passed to any code expecting a runtime-created account.
+pub struct TestAccount {
+    /// Memory region of the account.
+    ///
+    /// The region is allocated as `u64` values to guarantee the same alignment as
+    /// the input buffer provided by the runtime. It is never resized, so pointers
+    /// to it remain valid for the lifetime of the account.
+    memory: Vec<u64>,
+
+    /// Account info pointing to the memory region.
+    info: AccountInfo,
+}
+
+impl TestAccount {
+    /// Creates a new account.
+    ///
+    /// The account borrow state is initialized to represent a non-borrowed account, in
+    /// the same way as the entrypoint does when it deserializes the input buffer.
+    pub fn new(
+        key: Pubkey,
+        owner: Pubkey,
+        lamports: u64,
+        data: &[u8],
+        is_signer: bool,
+        is_writable: bool,
+        executable: bool,
+    ) -> Self {
+        let length = size_of::<Account>() + data.len() + MAX_PERMITTED_DATA_INCREASE;
+        let mut memory = std::vec![0u64; length.div_ceil(size_of::<u64>())];
+        let raw = memory.as_mut_ptr() as *mut Account;
+
+        unsafe {
+            raw.write(Account {
+                borrow_state: 0,
+                is_signer: is_signer as u8,
+                is_writable: is_writable as u8,
+                executable: executable as u8,
+                original_data_len: 0,
+                key,
+                owner,
+                lamports,
+                data_len: data.len() as u64,
+            });
+
+            core::ptr::copy_nonoverlapping(
+                data.as_ptr(),
+                (raw as *mut u8).add(size_of::<Account>()),
+                data.len(),
+            );
+        }
+
+        Self {
+            memory,
+            info: AccountInfo { raw },
+        }
+    }
+
+    /// Returns the [`AccountInfo`] of the account.
+    #[inline(always)]
+    pub fn info(&self) -> &AccountInfo {
+        &self.info
+    }
+
+    /// Returns the current data of the account.
+    ///
+    /// The data does not go through the borrow checks, so it reflects the current
+    /// state of the account even when there are active borrows.
+    pub fn data(&self) -> &[u8] {
+        unsafe { self.info.borrow_data_unchecked() }
+    }
+
+    /// Returns the number of bytes the account data can grow to.
+    ///
+    /// This is the original data length plus [`MAX_PERMITTED_DATA_INCREASE`].
+    pub fn capacity(&self) -> usize {
+        self.memory.len() * size_of::<u64>() - size_of::<Account>()
+    }
+
+    /// Returns a copy of the account with the given owner, lamports and data.
+    pub(crate) fn with_state(&self, owner: &Pubkey, lamports: u64, data: &[u8]) -> Self {
+        Self::new(
+            *self.key(),
+            *owner,
+            lamports,
+            data,
+            self.is_signer(),
+            self.is_writable(),
+            self.executable(),
+        )
+    }
+
+    /// Sets the signer and writable flags of the account.
+    pub(crate) fn set_privileges(&mut self, is_signer: bool, is_writable: bool) {
+        unsafe {
+            (*self.info.raw).is_signer = is_signer as u8;
+            (*self.info.raw).is_writable = is_writable as u8;
+        }
+    }
+
+    /// Sets the lamports of the account.
+    pub(crate) fn set_lamports(&mut self, lamports: u64) {
+        unsafe { (*self.info.raw).lamports = lamports };
+    }
+
+    /// Sets the owner of the account.
+    pub(crate) fn set_owner(&mut self, owner: &Pubkey) {
+        unsafe { (*self.info.raw).owner = *owner };
+    }
+
+    /// Returns the data of the account as a mutable slice.
+    pub(crate) fn data_mut(&mut self) -> &mut [u8] {
+        unsafe { self.info.borrow_mut_data_unchecked() }
+    }
+
+    /// Resizes the account data, zero-initializing any new bytes.
+    ///
+    /// Unlike [`AccountInfo::realloc`], the length is not limited by the capacity of
+    /// the account since a new memory region is allocated when needed.
+    pub(crate) fn resize(&mut self, len: usize) {
+        let mut data = self.data().to_vec();
+        data.resize(len, 0);
+        *self = self.with_state(self.owner(), self.lamports(), &data);
+    }
+}
+
+impl Clone for TestAccount {
+    /// Creates a copy of the account with its own memory region.
+    ///
+    /// The borrow state of the copy is reset to represent a non-borrowed account.
+    fn clone(&self) -> Self {
+        let mut memory = self.memory.clone();
+        let raw = memory.as_mut_ptr() as *mut Account;
+
+        unsafe {
+            (*raw).borrow_state = 0;
+        }
+
+        Self {
+            memory,
+            info: AccountInfo { raw },
+        }
+    }
+}
+
+impl Deref for TestAccount {
+    type Target = AccountInfo;
+
+    fn deref(&self) -> &Self::Target {
+        &self.info
+    }
+}
+
+impl AsRef<AccountInfo> for TestAccount {
+    fn as_ref(&self) -> &AccountInfo {
+        &self.info
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{instruction::AccountMeta, program_error::ProgramError};
+
+    #[test]
+    fn test_account_layout() {
+        let account = AccountBuilder::new([1; 32])
+            .owner([2; 32])
+            .lamports(100)
+            .data(&[1, 2, 3])
+            .signer(true)
+            .writable(true)
+            .build();
+
+        assert_eq!(account.key(), &[1; 32]);
+        assert_eq!(account.owner(), &[2; 32]);
+        assert_eq!(account.lamports(), 100);
+        assert_eq!(account.data(), &[1, 2, 3]);
+        assert!(account.is_signer());
+        assert!(account.is_writable());
+        assert!(!account.executable());
+        assert!(account.capacity() >= 3 + MAX_PERMITTED_DATA_INCREASE);
+
+        let meta = AccountMeta::from(account.info());
+        assert_eq!(meta.pubkey, &[1; 32]);
+        assert!(meta.is_signer);
+        assert!(meta.is_writable);
+    }
+
+    #[test]
+    fn test_realloc() {
+        let account = AccountBuilder::new([1; 32]).data(&[1; 8]).build();
+
+        account.realloc(4, false).unwrap();
+        assert_eq!(account.data(), &[1; 4]);
+
+        // Growing back without zero-initializing the memory exposes the stale data.
+        account.realloc(8, false).unwrap();
+        assert_eq!(account.data(), &[1; 8]);
+
+        account.realloc(4, false).unwrap();
+        account.realloc(8, true).unwrap();
+        assert_eq!(account.data(), &[1, 1, 1, 1, 0, 0, 0, 0]);
+
+        account
+            .realloc(8 + MAX_PERMITTED_DATA_INCREASE, true)
+            .unwrap();
+        assert_eq!(account.data_len(), 8 + MAX_PERMITTED_DATA_INCREASE);
+
+        assert_eq!(
+            account.realloc(9 + MAX_PERMITTED_DATA_INCREASE, true),
+            Err(ProgramError::InvalidRealloc)
+        );
+
+        let data = account.try_borrow_data().unwrap();
+        assert_eq!(
+            account.realloc(0, false),
+            Err(ProgramError::AccountBorrowFailed)
+        );
+        drop(data);
+    }
+
+    #[test]
+    fn test_close() {
+        let account = AccountBuilder::new([1; 32])
+            .owner([2; 32])
+            .lamports(100)
+            .data(&[1; 8])
+            .build();
+
+        let data = account.try_borrow_mut_data().unwrap();
+        assert_eq!(account.close(), Err(ProgramError::AccountBorrowFailed));
+        drop(data);
+
+        account.close().unwrap();
+
+        assert_eq!(account.owner(), &[0; 32]);
+        assert_eq!(account.lamports(), 0);
+        assert_eq!(account.data_len(), 0);
+    }
+
+    #[test]
+    fn test_borrows() {
+        let account = AccountBuilder::new([1; 32]).lamports(10).build();
+        // A duplicated account shares the same memory region.
+        let duplicated = account.info().clone();
+
+        let lamports = account.try_borrow_lamports().unwrap();
+        assert!(duplicated.try_borrow_mut_lamports().is_err());
+        assert!(duplicated.try_borrow_mut_data().is_ok());
+        drop(lamports);
+
+        let mut lamports = duplicated.try_borrow_mut_lamports().unwrap();
+        *lamports = 20;
+        assert!(account.try_borrow_lamports().is_err());
+        drop(lamports);
+
+        assert_eq!(account.lamports(), 20);
+
+        // A cloned test account has its own memory.
+        let copy = account.clone();
+        let _lamports = account.try_borrow_mut_lamports().unwrap();
+        assert!(copy.try_borrow_mut_lamports().is_ok());
+    }
+}
@@ -0,0 +1,966 @@
+//! In-process execution of instructions across multiple programs.
+
+use core::{cell::RefCell, mem::MaybeUninit};
+use std::{rc::Rc, vec::Vec};
+
+use crate::{
+    account_info::{self, AccountInfo},
+    entrypoint::deserialize,
+    instruction::{Account, AccountMeta, Instruction, Signer},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvars::{
+        clock::Clock,
+        rent::{
+            Rent, DEFAULT_BURN_PERCENT, DEFAULT_EXEMPTION_THRESHOLD, DEFAULT_LAMPORTS_PER_BYTE_YEAR,
+        },
+    },
+    ProgramResult, MAX_TX_ACCOUNTS, SUCCESS,
+};
+
+use super::{serialize, set_syscall_stubs, system, RecordingStubs, SyscallStubs, TestAccount};
+
+/// Maximum height of the invocation stack, including the top-level instruction.
+const MAX_INVOKE_STACK_HEIGHT: usize = 5;
+
+/// Signature of the `entrypoint` function created by the entrypoint macros.
+pub type Entrypoint = unsafe extern "C" fn(*mut u8) -> u64;
+
+/// Signature of a program instruction processor.
+pub type ProcessInstruction = fn(&Pubkey, &[AccountInfo], &[u8]) -> ProgramResult;
+
+/// Signature of a program executed directly on the instruction accounts.
+///
+/// The accounts are deduplicated; the indices map each account of the instruction
+/// to its position in the accounts slice.
+pub(crate) type Builtin = fn(&mut [TestAccount], &[usize], &[u8]) -> ProgramResult;
+
+/// A program registered with the executor.
+#[derive(Clone, Copy)]
+enum Program {
+    Entrypoint(Entrypoint),
+    Processor(ProcessInstruction),
+    Builtin(Builtin),
+}
+
+/// Errors reported by the [`Executor`].
+///
+/// Besides the errors returned by programs, the executor verifies the changes
+/// made to accounts in the same way as the runtime does.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum InstructionError {
+    /// A program returned an error.
+    Program(ProgramError),
+
+    /// The program is not registered with the executor.
+    UnsupportedProgramId(Pubkey),
+
+    /// An account required by an instruction was not provided.
+    MissingAccount(Pubkey),
+
+    /// A cross-program invocation requested a signer or writable privilege
+    /// the caller does not have.
+    PrivilegeEscalation(Pubkey),
+
+    /// The lamports of a read-only account were changed.
+    ReadonlyLamportChange(Pubkey),
+
+    /// The data of a read-only account was changed.
+    ReadonlyDataModified(Pubkey),
+
+    /// A program debited lamports from an account it does not own.
+    ExternalAccountLamportSpend(Pubkey),
+
+    /// A program modified the data of an account it does not own.
+    ExternalAccountDataModified(Pubkey),
+
+    /// The owner of an account was changed by a program that does not own it,
+    /// or the account data was not zeroed.
+    ModifiedProgramId(Pubkey),
+
+    /// The sum of the lamports of the accounts changed.
+    UnbalancedInstruction,
+
+    /// The data of an account grew beyond the permitted increase.
+    InvalidRealloc(Pubkey),
+
+    /// The maximum height of the invocation stack was exceeded.
+    CallDepth,
+
+    /// A program was invoked while it was already on the invocation stack.
+    ReentrancyNotAllowed,
+}
+
+impl From<ProgramError> for InstructionError {
+    fn from(error: ProgramError) -> Self {
+        InstructionError::Program(error)
+    }
+}
+
+/// Returns the `Rent` sysvar with the default runtime values, as set by [`Executor::new`].
+pub fn default_rent() -> Rent {
+    Rent {
+        lamports_per_byte_year: DEFAULT_LAMPORTS_PER_BYTE_YEAR,
+        exemption_threshold: DEFAULT_EXEMPTION_THRESHOLD,
+        burn_percent: DEFAULT_BURN_PERCENT,
+    }
+}
+
+/// Executes instructions against native builds of programs.
+///
+/// Programs are registered by their entrypoint or instruction processor.
+/// While an instruction is processed, the executor is set as the syscall stubs
+/// of the current thread, so cross-program invocations are routed to the
+/// registered programs. Signer and writable privileges are propagated to
+/// invoked programs, PDA signers are derived from the `Signer` seeds, and
+/// the changes made to accounts are verified after each program returns.
+///
+/// The System program is available by default. Logs, return data and sysvars
+/// are handled by a [`RecordingStubs`], accessible through [`Executor::stubs`].
+///
+/// # Example
+///
+/// ```ignore
+/// use pinocchio::testing::{AccountBuilder, Executor};
+///
+/// let mut executor = Executor::new();
+/// executor.add_entrypoint(PROGRAM_ID, entrypoint);
+///
+/// let mut accounts = [AccountBuilder::new(PAYER).lamports(1_000_000).build()];
+/// executor
+///     .process_instruction(&instruction, &mut accounts)
+///     .unwrap();
+/// ```
+pub struct Executor {
+    /// State shared with the syscall stubs.
+    state: Rc<ExecutorState>,
+}
+
+impl Default for Executor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Executor {
+    /// Creates a new executor with the System program registered.
+    ///
+    /// The `Rent` sysvar is set to the default runtime values and the `Clock`
+    /// sysvar to its default value.
+    pub fn new() -> Self {
+        let stubs = RecordingStubs::default();
+        stubs.set_clock(Clock::default());
+        stubs.set_rent(default_rent());
+
+        let state = ExecutorState {
+            programs: RefCell::default(),
+            stack: RefCell::default(),
+            error: RefCell::default(),
+            stubs,
+        };
+        state
+            .programs
+            .borrow_mut()
+            .push((system::ID, Program::Builtin(system::process_instruction)));
+
+        Self {
+            state: Rc::new(state),
+        }
+    }
+
+    /// Registers a program by its `entrypoint` function.
+    ///
+    /// Any program previously registered with the same id is replaced.
+    pub fn add_entrypoint(&mut self, program_id: Pubkey, entrypoint: Entrypoint) -> &mut Self {
+        self.add_program(program_id, Program::Entrypoint(entrypoint))
+    }
+
+    /// Registers a program by its instruction processor.
+    ///
+    /// Any program previously registered with the same id is replaced.
+    pub fn add_processor(
+        &mut self,
+        program_id: Pubkey,
+        processor: ProcessInstruction,
+    ) -> &mut Self {
+        self.add_program(program_id, Program::Processor(processor))
+    }
+
+    /// Returns the stubs handling logs, return data and sysvars.
+    pub fn stubs(&self) -> &RecordingStubs {
+        &self.state.stubs
+    }
+
+    /// Processes an instruction.
+    ///
+    /// The `accounts` must contain every account referenced by the instruction,
+    /// matched by key; the signer and writable privileges are taken from the
+    /// instruction. The changes are written back to `accounts` only when the
+    /// instruction succeeds.
+    ///
+    /// A failed cross-program invocation aborts the instruction with the error of the
+    /// invocation, even if the program ignores it and returns successfully.
+    pub fn process_instruction(
+        &self,
+        instruction: &Instruction,
+        accounts: &mut [TestAccount],
+    ) -> Result<(), InstructionError> {
+        let (pre, indices) = prepare(instruction.accounts, |key| {
+            accounts
+                .iter()
+                .find(|account| account.key() == key)
+                .cloned()
+        })?;
+
+        let state = &self.state;
+        let _guard = StubsGuard(Some(set_syscall_stubs(state.clone())));
+
+        state.stack.borrow_mut().clear();
+        state.error.borrow_mut().take();
+        state.stubs.clear_return_data();
+
+        let result = state.run(instruction.program_id, &pre, &indices, instruction.data);
+
+        // A failed cross-program invocation aborts the program on-chain, so its error
+        // takes precedence over the result returned by the program.
+        if let Some(error) = state.error.borrow_mut().take() {
+            return Err(error);
+        }
+
+        let post = result?;
+
+        for post in post {
+            if let Some(account) = accounts.iter_mut().find(|a| a.key() == post.key()) {
+                *account = account.with_state(post.owner(), post.lamports(), post.data());
+            }
+        }
+
+        Ok(())
+    }
+
+    fn add_program(&mut self, program_id: Pubkey, program: Program) -> &mut Self {
+        let mut programs = self.state.programs.borrow_mut();
+        programs.retain(|(id, _)| *id != program_id);
+        programs.push((program_id, program));
+        drop(programs);
+        self
+    }
+}
+
+/// Restores the previous syscall stubs when dropped.
+struct StubsGuard(Option<Rc<dyn SyscallStubs>>);
+
+impl Drop for StubsGuard {
+    fn drop(&mut self) {
+        if let Some(stubs) = self.0.take() {
+            set_syscall_stubs(stubs);
+        }
+    }
+}
+
+/// State of the executor shared with the syscall stubs.
+struct ExecutorState {
+    /// Registered programs.
+    programs: RefCell<Vec<(Pubkey, Program)>>,
+
+    /// Programs on the invocation stack.
+    stack: RefCell<Vec<Frame>>,
+
+    /// Error of a failed cross-program invocation.
+    error: RefCell<Option<InstructionError>>,
+
+    /// Stubs for logs, return data and sysvars.
+    stubs: RecordingStubs,
+}
+
+/// A program on the invocation stack.
+struct Frame {
+    /// Id of the program.
+    program_id: Pubkey,
+
+    /// State of the accounts of the instruction as last verified.
+    ///
+    /// The state is updated after each cross-program invocation, since the changes
+    /// made by the invoked program are verified against its own privileges.
+    accounts: Vec<TestAccount>,
+}
+
+impl ExecutorState {
+    /// Runs a program and verifies the changes made to the accounts.
+    ///
+    /// `pre` contains the deduplicated accounts of the instruction and `indices`
+    /// maps each instruction account to its position in `pre`. Returns the
+    /// accounts after the execution, in the same order as `pre`.
+    fn run(
+        &self,
+        program_id: &Pubkey,
+        pre: &[TestAccount],
+        indices: &[usize],
+        data: &[u8],
+    ) -> Result<Vec<TestAccount>, InstructionError> {
+        let program = self
+            .programs
+            .borrow()
+            .iter()
+            .find(|(id, _)| id == program_id)
+            .map(|(_, program)| *program)
+            .ok_or(InstructionError::UnsupportedProgramId(*program_id))?;
+
+        {
+            let stack = self.stack.borrow();
+            if stack.len() >= MAX_INVOKE_STACK_HEIGHT {
+                return Err(InstructionError::CallDepth);
+            }
+            // Only direct recursion is allowed.
+            if stack.iter().any(|frame| &frame.program_id == program_id)
+                && stack.last().map(|frame| &frame.program_id) != Some(program_id)
+            {
+                return Err(InstructionError::ReentrancyNotAllowed);
+            }
+        }
+
+        self.stack.borrow_mut().push(Frame {
+            program_id: *program_id,
+            accounts: pre.to_vec(),
+        });
+        self.stubs.set_program_id(*program_id);
+
+        let result = execute(program, program_id, pre, indices, data);
+
+        let frame = self.stack.borrow_mut().pop().unwrap();
+        if let Some(caller) = self.stack.borrow().last() {
+            self.stubs.set_program_id(caller.program_id);
+        }
+
+        let post = result?;
+
+        for (pre, post) in frame.accounts.iter().zip(&post) {
+            verify(program_id, pre, post)?;
+        }
+
+        let pre_lamports = pre.iter().map(|a| a.lamports() as u128).sum::<u128>();
+        let post_lamports = post.iter().map(|a| a.lamports() as u128).sum::<u128>();
+
+        if pre_lamports != post_lamports {
+            return Err(InstructionError::UnbalancedInstruction);
+        }
+
+        Ok(post)
+    }
+
+    /// Processes a cross-program invocation from the program on top of the stack.
+    fn invoke(
+        &self,
+        instruction: &Instruction,
+        accounts: &[Account],
+        signers: &[Signer],
+    ) -> Result<(), InstructionError> {
+        let caller = self
+            .stack
+            .borrow()
+            .last()
+            .map(|frame| frame.program_id)
+            .expect("cross-program invocation outside of an instruction");
+
+        let mut pda_signers = Vec::with_capacity(signers.len());
+        for signer in signers {
+            let seeds = unsafe { core::slice::from_raw_parts(signer.seeds, signer.len as usize) };
+            let seeds = seeds.iter().map(|seed| &**seed).collect::<Vec<_>>();
+            pda_signers.push(self.sol_create_program_address(&seeds, &caller)?);
+        }
+
+        let caller_account = |key: &Pubkey| {
+            accounts
+                .iter()
+                .find(|account| unsafe { &*account.key } == key)
+        };
+
+        let (callee_accounts, indices) = prepare(instruction.accounts, |key| {
+            caller_account(key).map(|account| unsafe { to_test_account(account) })
+        })?;
+
+        for account in &callee_accounts {
+            // The account exists, since it was found when preparing the accounts.
+            let caller_account = caller_account(account.key()).unwrap();
+
+            if (account.is_writable() && !caller_account.is_writable)
+                || (account.is_signer()
+                    && !caller_account.is_signer
+                    && !pda_signers.contains(account.key()))
+            {
+                return Err(InstructionError::PrivilegeEscalation(*account.key()));
+            }
+        }
+
+        // The changes made by the caller so far are verified before the invocation.
+        for account in &callee_accounts {
+            let stack = self.stack.borrow();
+            let frame = stack.last().unwrap();
+            if let Some(pre) = frame.accounts.iter().find(|a| a.key() == account.key()) {
+                verify(&caller, pre, account)?;
+            }
+        }
+
+        self.stubs.clear_return_data();
+
+        let post = self.run(
+            instruction.program_id,
+            &callee_accounts,
+            &indices,
+            instruction.data,
+        )?;
+
+        for (pre, post) in callee_accounts.iter().zip(post) {
+            if pre.is_writable() {
+                let account = caller_account(post.key()).unwrap();
+                unsafe { write_back(account, &post)? };
+            }
+
+            let mut stack = self.stack.borrow_mut();
+            let frame = stack.last_mut().unwrap();
+            if let Some(account) = frame.accounts.iter_mut().find(|a| a.key() == post.key()) {
+                *account = account.with_state(post.owner(), post.lamports(), post.data());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl SyscallStubs for ExecutorState {
+    fn sol_log(&self, message: &str) {
+        self.stubs.sol_log(message);
+    }
+
+    fn sol_log_64(&self, arg1: u64, arg2: u64, arg3: u64, arg4: u64, arg5: u64) {
+        self.stubs.sol_log_64(arg1, arg2, arg3, arg4, arg5);
+    }
+
+    fn sol_log_pubkey(&self, pubkey: &Pubkey) {
+        self.stubs.sol_log_pubkey(pubkey);
+    }
+
+    fn sol_log_data(&self, data: &[&[u8]]) {
+        self.stubs.sol_log_data(data);
+    }
+
+    fn sol_log_compute_units(&self) {
+        self.stubs.sol_log_compute_units();
+    }
+
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        accounts: &[Account],
+        signers: &[Signer],
+    ) {
+        // The program would have been aborted by the first failed invocation.
+        if self.error.borrow().is_some() {
+            return;
+        }
+
+        if let Err(error) = self.invoke(instruction, accounts, signers) {
+            self.error.borrow_mut().replace(error);
+        }
+    }
+
+    fn sol_set_return_data(&self, data: &[u8]) {
+        self.stubs.sol_set_return_data(data);
+    }
+
+    fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        self.stubs.sol_get_return_data()
+    }
+
+    unsafe fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.stubs.sol_get_clock_sysvar(var_addr)
+    }
+
+    unsafe fn sol_get_fees_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.stubs.sol_get_fees_sysvar(var_addr)
+    }
+
+    unsafe fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.stubs.sol_get_rent_sysvar(var_addr)
+    }
+}
+
+/// Deduplicates the accounts of an instruction.
+///
+/// Returns the accounts, with their privileges set from the account metas, and the
+/// position of each account meta in the returned accounts.
+fn prepare(
+    metas: &[AccountMeta],
+    mut lookup: impl FnMut(&Pubkey) -> Option<TestAccount>,
+) -> Result<(Vec<TestAccount>, Vec<usize>), InstructionError> {
+    let mut accounts: Vec<TestAccount> = Vec::with_capacity(metas.len());
+    let mut indices = Vec::with_capacity(metas.len());
+
+    for meta in metas {
+        match accounts.iter().position(|a| a.key() == meta.pubkey) {
+            Some(index) => {
+                let account = &mut accounts[index];
+                let is_signer = account.is_signer() || meta.is_signer;
+                let is_writable = account.is_writable() || meta.is_writable;
+                account.set_privileges(is_signer, is_writable);
+                indices.push(index);
+            }
+            None => {
+                let mut account =
+                    lookup(meta.pubkey).ok_or(InstructionError::MissingAccount(*meta.pubkey))?;
+                account.set_privileges(meta.is_signer, meta.is_writable);
+                indices.push(accounts.len());
+                accounts.push(account);
+            }
+        }
+    }
+
+    Ok((accounts, indices))
+}
+
+/// Executes a program, returning the accounts after the execution.
+fn execute(
+    program: Program,
+    program_id: &Pubkey,
+    pre: &[TestAccount],
+    indices: &[usize],
+    data: &[u8],
+) -> Result<Vec<TestAccount>, InstructionError> {
+    let (entrypoint, processor) = match program {
+        Program::Builtin(builtin) => {
+            let mut post = pre.to_vec();
+            builtin(&mut post, indices, data)?;
+            return Ok(post);
+        }
+        Program::Entrypoint(entrypoint) => (Some(entrypoint), None),
+        Program::Processor(processor) => (None, Some(processor)),
+    };
+
+    let accounts = indices
+        .iter()
+        .map(|index| pre[*index].clone())
+        .collect::<Vec<_>>();
+    let mut input = serialize(program_id, &accounts, data);
+
+    if let Some(entrypoint) = entrypoint {
+        let result = unsafe { entrypoint(input.as_mut_ptr()) };
+        if result != SUCCESS {
+            return Err(InstructionError::Program(result.into()));
+        }
+    }
+
+    if let Some(processor) = processor {
+        const UNINIT: MaybeUninit<AccountInfo> = MaybeUninit::<AccountInfo>::uninit();
+        let mut infos = [UNINIT; MAX_TX_ACCOUNTS];
+
+        let (program_id, count, data) =
+            unsafe { deserialize::<MAX_TX_ACCOUNTS>(input.as_mut_ptr(), &mut infos) };
+        let infos = unsafe { core::slice::from_raw_parts(infos.as_ptr() as _, count) };
+
+        processor(program_id, infos, data)?;
+    }
+
+    Ok(pre
+        .iter()
+        .enumerate()
+        .map(|(index, account)| {
+            // Every account is referenced by at least one account meta.
+            let position = indices.iter().position(|i| *i == index).unwrap();
+            account.with_state(
+                input.owner(position),
+                input.lamports(position),
+                input.data(position),
+            )
+        })
+        .collect())
+}
+
+/// Verifies the changes made by a program to an account.
+fn verify(
+    program_id: &Pubkey,
+    pre: &TestAccount,
+    post: &TestAccount,
+) -> Result<(), InstructionError> {
+    let key = *pre.key();
+    let is_owner = pre.owner() == program_id;
+
+    if pre.owner() != post.owner()
+        && (!pre.is_writable() || !is_owner || post.data().iter().any(|b| *b != 0))
+    {
+        return Err(InstructionError::ModifiedProgramId(key));
+    }
+
+    if pre.lamports() != post.lamports() {
+        if !pre.is_writable() {
+            return Err(InstructionError::ReadonlyLamportChange(key));
+        }
+        if post.lamports() < pre.lamports() && !is_owner {
+            return Err(InstructionError::ExternalAccountLamportSpend(key));
+        }
+    }
+
+    if pre.data() != post.data() {
+        if !pre.is_writable() {
+            return Err(InstructionError::ReadonlyDataModified(key));
+        }
+        if !is_owner {
+            return Err(InstructionError::ExternalAccountDataModified(key));
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the account header of a cross-program invocation account.
+unsafe fn header(account: &Account) -> *mut account_info::Account {
+    // The key is at offset `8` of the account header.
+    (account.key as *mut u8).sub(8) as *mut account_info::Account
+}
+
+/// Creates a test account from the memory of a cross-program invocation account.
+unsafe fn to_test_account(account: &Account) -> TestAccount {
+    let header = header(account);
+    let data = core::slice::from_raw_parts(
+        (header as *const u8).add(core::mem::size_of::<account_info::Account>()),
+        (*header).data_len as usize,
+    );
+
+    TestAccount::new(
+        (*header).key,
+        (*header).owner,
+        (*header).lamports,
+        data,
+        (*header).is_signer != 0,
+        (*header).is_writable != 0,
+        (*header).executable != 0,
+    )
+}
+
+/// Writes the state of an account back to the memory of the caller.
+unsafe fn write_back(account: &Account, post: &TestAccount) -> Result<(), InstructionError> {
+    let info = AccountInfo {
+        raw: header(account),
+    };
+    let data = post.data();
+
+    // `realloc` limits the length to the permitted increase over the original length,
+    // which is what the caller memory can hold.
+    if data.len() != info.data_len() {
+        info.realloc(data.len(), false)
+            .map_err(|_| InstructionError::InvalidRealloc(*post.key()))?;
+    }
+
+    let raw = info.raw;
+    (*raw).lamports = post.lamports();
+    (*raw).owner = *post.owner();
+    info.borrow_mut_data_unchecked().copy_from_slice(data);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        account_info::MAX_PERMITTED_DATA_INCREASE, program::invoke_signed,
+        pubkey::find_program_address, signer, testing::AccountBuilder,
+    };
+
+    const PAYER: Pubkey = [1; 32];
+    const RECIPIENT: Pubkey = [2; 32];
+    const CALLER: Pubkey = [3; 32];
+
+    fn transfer_data(lamports: u64) -> [u8; 12] {
+        let mut data = [0; 12];
+        data[..4].copy_from_slice(&2u32.to_le_bytes());
+        data[4..].copy_from_slice(&lamports.to_le_bytes());
+        data
+    }
+
+    /// Transfers lamports from a PDA of the program using the System program.
+    fn caller(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+        let [vault, recipient, ..] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        let (_, bump) = find_program_address(&[b"vault"], program_id);
+        let bump = [bump];
+
+        let metas = [
+            AccountMeta::writable_signer(vault.key()),
+            AccountMeta::writable(recipient.key()),
+        ];
+        let data = transfer_data(u64::from_le_bytes(data.try_into().unwrap()));
+        let instruction = Instruction {
+            program_id: &system::ID,
+            accounts: &metas,
+            data: &data,
+        };
+
+        invoke_signed(
+            &instruction,
+            &[vault, recipient],
+            &[signer!(b"vault", &bump)],
+        )
+    }
+
+    #[test]
+    fn test_system_transfer() {
+        let executor = Executor::new();
+        let mut accounts = [
+            AccountBuilder::new(PAYER).lamports(1_000).build(),
+            AccountBuilder::new(RECIPIENT).build(),
+        ];
+        let data = transfer_data(400);
+
+        let metas = [
+            AccountMeta::writable_signer(&PAYER),
+            AccountMeta::writable(&RECIPIENT),
+        ];
+        let instruction = Instruction {
+            program_id: &system::ID,
+            accounts: &metas,
+            data: &data,
+        };
+        executor
+            .process_instruction(&instruction, &mut accounts)
+            .unwrap();
+
+        assert_eq!(accounts[0].lamports(), 600);
+        assert_eq!(accounts[1].lamports(), 400);
+
+        let metas = [
+            AccountMeta::writable(&PAYER),
+            AccountMeta::writable(&RECIPIENT),
+        ];
+        let instruction = Instruction {
+            program_id: &system::ID,
+            accounts: &metas,
+            data: &data,
+        };
+        assert_eq!(
+            executor.process_instruction(&instruction, &mut accounts),
+            Err(InstructionError::Program(
+                ProgramError::MissingRequiredSignature
+            ))
+        );
+        // Failed instructions do not modify the accounts.
+        assert_eq!(accounts[0].lamports(), 600);
+    }
+
+    #[test]
+    fn test_create_account() {
+        let executor = Executor::new();
+        let mut accounts = [
+            AccountBuilder::new(PAYER).lamports(1_000_000).build(),
+            AccountBuilder::new(RECIPIENT).build(),
+        ];
+
+        // Accounts larger than the permitted increase can be created.
+        let space = 2 * MAX_PERMITTED_DATA_INCREASE as u64;
+        let mut data = [0; 52];
+        data[4..12].copy_from_slice(&1_000u64.to_le_bytes());
+        data[12..20].copy_from_slice(&space.to_le_bytes());
+        data[20..].copy_from_slice(&CALLER);
+
+        let metas = [
+            AccountMeta::writable_signer(&PAYER),
+            AccountMeta::writable_signer(&RECIPIENT),
+        ];
+        let instruction = Instruction {
+            program_id: &system::ID,
+            accounts: &metas,
+            data: &data,
+        };
+        executor
+            .process_instruction(&instruction, &mut accounts)
+            .unwrap();
+
+        assert_eq!(accounts[1].owner(), &CALLER);
+        assert_eq!(accounts[1].lamports(), 1_000);
+        assert_eq!(accounts[1].data_len(), space as usize);
+
+        assert_eq!(
+            executor.process_instruction(&instruction, &mut accounts),
+            Err(InstructionError::Program(ProgramError::Custom(0)))
+        );
+    }
+
+    #[test]
+    fn test_invoke_signed() {
+        let mut executor = Executor::new();
+        executor.add_processor(CALLER, caller);
+
+        let (vault, _) = find_program_address(&[b"vault"], &CALLER);
+        let mut accounts = [
+            AccountBuilder::new(vault).lamports(1_000).build(),
+            AccountBuilder::new(RECIPIENT).build(),
+            AccountBuilder::new(PAYER).lamports(1_000).build(),
+        ];
+        let data = 250u64.to_le_bytes();
+
+        let metas = [
+            AccountMeta::writable(&vault),
+            AccountMeta::writable(&RECIPIENT),
+            AccountMeta::readonly(&system::ID),
+        ];
+        let instruction = Instruction {
+            program_id: &CALLER,
+            accounts: &metas,
+            data: &data,
+        };
+        let mut with_program = [
+            accounts[0].clone(),
+            accounts[1].clone(),
+            AccountBuilder::new(system::ID).executable(true).build(),
+        ];
+        executor
+            .process_instruction(&instruction, &mut with_program)
+            .unwrap();
+
+        assert_eq!(with_program[0].lamports(), 750);
+        assert_eq!(with_program[1].lamports(), 250);
+
+        // Errors of the invoked program are returned by the executor.
+        let data = 1_000u64.to_le_bytes();
+        let instruction = Instruction {
+            program_id: &CALLER,
+            accounts: &metas,
+            data: &data,
+        };
+        assert_eq!(
+            executor.process_instruction(&instruction, &mut with_program),
+            Err(InstructionError::Program(ProgramError::Custom(1)))
+        );
+
+        // The payer is not a PDA of the program, so it cannot be signed for.
+        let metas = [
+            AccountMeta::writable(&PAYER),
+            AccountMeta::writable(&RECIPIENT),
+        ];
+        let instruction = Instruction {
+            program_id: &CALLER,
+            accounts: &metas,
+            data: &data,
+        };
+        assert_eq!(
+            executor.process_instruction(&instruction, &mut accounts),
+            Err(InstructionError::PrivilegeEscalation(PAYER))
+        );
+    }
+
+    #[test]
+    fn test_entrypoint_failed_invoke() {
+        /// Entrypoint of the `caller` program, as created by the entrypoint macros.
+        unsafe extern "C" fn entrypoint(input: *mut u8) -> u64 {
+            const UNINIT: MaybeUninit<AccountInfo> = MaybeUninit::<AccountInfo>::uninit();
+            let mut accounts = [UNINIT; 3];
+
+            let (program_id, count, data) = deserialize::<3>(input, &mut accounts);
+
+            match caller(
+                program_id,
+                core::slice::from_raw_parts(accounts.as_ptr() as _, count),
+                data,
+            ) {
+                Ok(()) => SUCCESS,
+                Err(error) => error.into(),
+            }
+        }
+
+        let mut executor = Executor::new();
+        executor.add_entrypoint(CALLER, entrypoint);
+
+        let (vault, _) = find_program_address(&[b"vault"], &CALLER);
+        let mut accounts = [
+            AccountBuilder::new(vault).lamports(1_000).build(),
+            AccountBuilder::new(RECIPIENT).build(),
+            AccountBuilder::new(system::ID).executable(true).build(),
+        ];
+        let metas = [
+            AccountMeta::writable(&vault),
+            AccountMeta::writable(&RECIPIENT),
+            AccountMeta::readonly(&system::ID),
+        ];
+
+        // The transfer fails and the program returns successfully, as a failed
+        // invocation does not return an error on host builds.
+        let data = 2_000u64.to_le_bytes();
+        let instruction = Instruction {
+            program_id: &CALLER,
+            accounts: &metas,
+            data: &data,
+        };
+        assert_eq!(
+            executor.process_instruction(&instruction, &mut accounts),
+            Err(InstructionError::Program(ProgramError::Custom(1)))
+        );
+        assert_eq!(accounts[0].lamports(), 1_000);
+
+        // The error is not carried over to the next instruction.
+        let data = 600u64.to_le_bytes();
+        let instruction = Instruction {
+            program_id: &CALLER,
+            accounts: &metas,
+            data: &data,
+        };
+        executor
+            .process_instruction(&instruction, &mut accounts)
+            .unwrap();
+        assert_eq!(accounts[0].lamports(), 400);
+        assert_eq!(accounts[1].lamports(), 600);
+    }
+
+    #[test]
+    fn test_verify() {
+        fn spend(_: &Pubkey, accounts: &[AccountInfo], _: &[u8]) -> ProgramResult {
+            *accounts[0].try_borrow_mut_lamports()? -= 1;
+            *accounts[1].try_borrow_mut_lamports()? += 1;
+            Ok(())
+        }
+
+        let mut executor = Executor::new();
+        executor.add_processor(CALLER, spend);
+
+        let mut accounts = [
+            AccountBuilder::new(PAYER).lamports(10).build(),
+            AccountBuilder::new(RECIPIENT).build(),
+        ];
+        let metas = [
+            AccountMeta::writable(&PAYER),
+            AccountMeta::writable(&RECIPIENT),
+        ];
+        let instruction = Instruction {
+            program_id: &CALLER,
+            accounts: &metas,
+            data: &[],
+        };
+
+        assert_eq!(
+            executor.process_instruction(&instruction, &mut accounts),
+            Err(InstructionError::ExternalAccountLamportSpend(PAYER))
+        );
+
+        accounts[0] = AccountBuilder::new(PAYER)
+            .owner(CALLER)
+            .lamports(10)
+            .build();
+        executor
+            .process_instruction(&instruction, &mut accounts)
+            .unwrap();
+        assert_eq!(accounts[1].lamports(), 1);
+
+        let metas = [
+            AccountMeta::writable(&PAYER),
+            AccountMeta::readonly(&RECIPIENT),
+        ];
+        let instruction = Instruction {
+            program_id: &CALLER,
+            accounts: &metas,
+            data: &[],
+        };
+        assert_eq!(
+            executor.process_instruction(&instruction, &mut accounts),
+            Err(InstructionError::ReadonlyLamportChange(RECIPIENT))
+        );
+    }
+}
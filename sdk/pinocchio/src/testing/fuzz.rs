@@ -0,0 +1,454 @@
+//! Models of the program input and account borrows for property-based testing.
+//!
+//! The models implement [`Arbitrary`], so they can be generated by fuzzers (e.g.,
+//! `cargo-fuzz` or `bolero`), and provide `check` methods that exercise the unsafe
+//! code paths &mdash; [`deserialize`], the lazy [`InstructionContext`] and the
+//! borrow tracking of [`AccountInfo`] &mdash; and compare the result with the model.
+//! Any discrepancy results in a panic.
+//!
+//! [`deserialize`]: crate::entrypoint::deserialize
+//! [`InstructionContext`]: crate::entrypoint::InstructionContext
+
+use core::mem::MaybeUninit;
+use std::vec::Vec;
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+use crate::{
+    account_info::{AccountInfo, Ref, RefMut},
+    entrypoint::{deserialize, InstructionContext, MaybeAccount},
+    pubkey::Pubkey,
+    MAX_TX_ACCOUNTS, NON_DUP_MARKER,
+};
+
+use super::{serialize, AccountBuilder, TestAccount};
+
+/// Maximum length of the account data generated.
+///
+/// Large enough to cover unaligned lengths, while keeping the input small.
+const MAX_DATA_LEN: usize = 256;
+
+/// Model of an account of the program input.
+#[derive(Clone, Debug)]
+pub enum AccountModel {
+    /// A non-duplicated account.
+    Account {
+        key: Pubkey,
+        owner: Pubkey,
+        lamports: u64,
+        data: Vec<u8>,
+        is_signer: bool,
+        is_writable: bool,
+        executable: bool,
+    },
+
+    /// A duplicate of the account at the given index.
+    Duplicated(u8),
+}
+
+/// Model of the program input serialized by the runtime.
+///
+/// Generated inputs include duplicated accounts, accounts with zero-length data
+/// and up to `255` accounts, so the limit of accounts processed by the entrypoint
+/// is also exercised.
+#[derive(Clone, Debug)]
+pub struct InputModel {
+    /// Id of the program.
+    pub program_id: Pubkey,
+
+    /// Accounts of the instruction.
+    pub accounts: Vec<AccountModel>,
+
+    /// Instruction data.
+    pub instruction_data: Vec<u8>,
+}
+
+impl<'a> Arbitrary<'a> for InputModel {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let program_id = u.arbitrary()?;
+
+        // Favour small inputs, but occasionally reach the maximum number of
+        // accounts the runtime can serialize.
+        let count = match u.int_in_range(0..=7u8)? {
+            0 => u.int_in_range(MAX_TX_ACCOUNTS..=NON_DUP_MARKER as usize)?,
+            _ => u.int_in_range(0..=8)?,
+        };
+
+        let mut accounts = Vec::with_capacity(count);
+
+        for index in 0..count {
+            if index > 0 && u.ratio(1, 4)? {
+                let original = u.int_in_range(0..=index - 1)?;
+                accounts.push(AccountModel::Duplicated(original as u8));
+                continue;
+            }
+
+            let mut key: Pubkey = u.arbitrary()?;
+            // Keys must be unique; otherwise, the account would be a duplicate.
+            key[..2].copy_from_slice(&(index as u16).to_le_bytes());
+
+            let data = match u.int_in_range(0..=2u8)? {
+                0 => Vec::new(),
+                _ => {
+                    let len = u.int_in_range(0..=MAX_DATA_LEN)?;
+                    u.bytes(len)?.to_vec()
+                }
+            };
+
+            accounts.push(AccountModel::Account {
+                key,
+                owner: u.arbitrary()?,
+                lamports: u.arbitrary()?,
+                data,
+                is_signer: u.arbitrary()?,
+                is_writable: u.arbitrary()?,
+                executable: u.arbitrary()?,
+            });
+        }
+
+        Ok(Self {
+            program_id,
+            accounts,
+            instruction_data: u.arbitrary()?,
+        })
+    }
+}
+
+impl InputModel {
+    /// Returns the index of the non-duplicated account represented by the account
+    /// at `index`.
+    pub fn original(&self, index: usize) -> usize {
+        match self.accounts[index] {
+            AccountModel::Account { .. } => index,
+            AccountModel::Duplicated(original) => self.original(original as usize),
+        }
+    }
+
+    /// Creates the [`TestAccount`]s represented by the model.
+    ///
+    /// Duplicated accounts are copies of the original account, so they are serialized
+    /// as duplicates.
+    pub fn test_accounts(&self) -> Vec<TestAccount> {
+        (0..self.accounts.len())
+            .map(|index| match &self.accounts[self.original(index)] {
+                AccountModel::Account {
+                    key,
+                    owner,
+                    lamports,
+                    data,
+                    is_signer,
+                    is_writable,
+                    executable,
+                } => AccountBuilder::new(*key)
+                    .owner(*owner)
+                    .lamports(*lamports)
+                    .data(data)
+                    .signer(*is_signer)
+                    .writable(*is_writable)
+                    .executable(*executable)
+                    .build(),
+                AccountModel::Duplicated(_) => unreachable!(),
+            })
+            .collect()
+    }
+
+    /// Checks that [`deserialize`] returns the accounts, instruction data and program
+    /// id of the model, processing at most `MAX_ACCOUNTS` accounts.
+    ///
+    /// [`deserialize`]: crate::entrypoint::deserialize
+    pub fn check_deserialize<const MAX_ACCOUNTS: usize>(&self) {
+        let accounts = self.test_accounts();
+        let mut input = serialize(&self.program_id, &accounts, &self.instruction_data);
+
+        const UNINIT: MaybeUninit<AccountInfo> = MaybeUninit::<AccountInfo>::uninit();
+        let mut infos = [UNINIT; MAX_ACCOUNTS];
+
+        let (program_id, count, instruction_data) =
+            unsafe { deserialize::<MAX_ACCOUNTS>(input.as_mut_ptr(), &mut infos) };
+
+        assert_eq!(program_id, &self.program_id);
+        assert_eq!(instruction_data, self.instruction_data.as_slice());
+        assert_eq!(count, core::cmp::min(self.accounts.len(), MAX_ACCOUNTS));
+
+        let infos =
+            unsafe { core::slice::from_raw_parts(infos.as_ptr() as *const AccountInfo, count) };
+
+        for (index, info) in infos.iter().enumerate() {
+            let original = self.original(index);
+            // Duplicated accounts share the memory of the original account.
+            assert!(info == &infos[original]);
+            check_account(info, &accounts[index]);
+        }
+    }
+
+    /// Checks that the lazy [`InstructionContext`] returns the accounts, instruction
+    /// data and program id of the model.
+    ///
+    /// [`InstructionContext`]: crate::entrypoint::InstructionContext
+    pub fn check_lazy(&self) {
+        let accounts = self.test_accounts();
+        let mut input = serialize(&self.program_id, &accounts, &self.instruction_data);
+
+        let mut context = InstructionContext::new(input.as_mut_ptr());
+        assert_eq!(context.available(), self.accounts.len() as u64);
+
+        for (index, model) in self.accounts.iter().enumerate() {
+            assert_eq!(context.remaining(), (self.accounts.len() - index) as u64);
+            // The instruction data is only available after all accounts are read.
+            assert!(context.instruction_data().is_err());
+
+            match (context.next_account().unwrap(), model) {
+                (MaybeAccount::Account(info), AccountModel::Account { .. }) => {
+                    check_account(&info, &accounts[index]);
+                }
+                (MaybeAccount::Duplicated(original), AccountModel::Duplicated(_)) => {
+                    assert_eq!(original as usize, self.original(index));
+                }
+                _ => panic!("account {index} does not match the model"),
+            }
+        }
+
+        assert!(context.next_account().is_err());
+        assert_eq!(
+            context.instruction_data().unwrap(),
+            self.instruction_data.as_slice()
+        );
+        assert_eq!(context.program_id().unwrap(), &self.program_id);
+    }
+}
+
+/// Checks that an account info matches the expected account.
+fn check_account(info: &AccountInfo, expected: &TestAccount) {
+    assert_eq!(info.key(), expected.key());
+    assert_eq!(info.owner(), expected.owner());
+    assert_eq!(info.lamports(), expected.lamports());
+    assert_eq!(info.is_signer(), expected.is_signer());
+    assert_eq!(info.is_writable(), expected.is_writable());
+    assert_eq!(info.executable(), expected.executable());
+    assert_eq!(unsafe { info.borrow_data_unchecked() }, expected.data());
+
+    // Accounts are not borrowed after deserialization.
+    assert!(info.check_borrow_mut_lamports().is_ok());
+    assert!(info.check_borrow_mut_data().is_ok());
+}
+
+/// An operation on the borrow state of an account.
+#[derive(Clone, Copy, Debug)]
+pub enum BorrowOp {
+    /// Borrows the lamports.
+    Lamports,
+
+    /// Mutably borrows the lamports.
+    LamportsMut,
+
+    /// Borrows the data.
+    Data,
+
+    /// Mutably borrows the data.
+    DataMut,
+
+    /// Drops the live borrow at the given position, if any.
+    Release(u8),
+}
+
+impl<'a> Arbitrary<'a> for BorrowOp {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(match u.int_in_range(0..=4u8)? {
+            0 => BorrowOp::Lamports,
+            1 => BorrowOp::LamportsMut,
+            2 => BorrowOp::Data,
+            3 => BorrowOp::DataMut,
+            _ => BorrowOp::Release(u.arbitrary()?),
+        })
+    }
+}
+
+/// Model of a sequence of borrows of an account.
+#[derive(Clone, Debug)]
+pub struct BorrowModel {
+    /// Operations applied in order.
+    pub ops: Vec<BorrowOp>,
+
+    /// Whether each operation uses a duplicated [`AccountInfo`].
+    ///
+    /// Duplicated account infos share the borrow state of the account.
+    pub duplicated: Vec<bool>,
+}
+
+impl<'a> Arbitrary<'a> for BorrowModel {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let ops: Vec<BorrowOp> = u.arbitrary()?;
+        let duplicated = (0..ops.len())
+            .map(|_| u.arbitrary())
+            .collect::<Result<_>>()?;
+
+        Ok(Self { ops, duplicated })
+    }
+}
+
+/// A live borrow.
+enum Borrow<'a> {
+    Lamports(Ref<'a, u64>),
+    LamportsMut(RefMut<'a, u64>),
+    Data(Ref<'a, [u8]>),
+    DataMut(RefMut<'a, [u8]>),
+}
+
+/// Reference state of the borrows of one field.
+#[derive(Default)]
+struct FieldState {
+    shared: u8,
+    exclusive: bool,
+}
+
+impl FieldState {
+    fn can_borrow(&self) -> bool {
+        !self.exclusive && self.shared < 7
+    }
+
+    fn can_borrow_mut(&self) -> bool {
+        !self.exclusive && self.shared == 0
+    }
+}
+
+impl BorrowModel {
+    /// Checks that the borrow tracking of an account follows the borrow rules.
+    ///
+    /// Each field (lamports and data) allows either up to `7` shared borrows or a
+    /// single mutable borrow, independently of the other field.
+    pub fn check(&self) {
+        let account = AccountBuilder::new([1; 32])
+            .lamports(1)
+            .data(&[1; 8])
+            .build();
+        let duplicate = account.info().clone();
+
+        let mut borrows = Vec::new();
+        let mut lamports = FieldState::default();
+        let mut data = FieldState::default();
+
+        for (op, duplicated) in self.ops.iter().zip(&self.duplicated) {
+            let info = if *duplicated {
+                &duplicate
+            } else {
+                account.info()
+            };
+
+            assert_eq!(info.check_borrow_lamports().is_ok(), lamports.can_borrow());
+            assert_eq!(
+                info.check_borrow_mut_lamports().is_ok(),
+                lamports.can_borrow_mut()
+            );
+            assert_eq!(info.check_borrow_data().is_ok(), data.can_borrow());
+            assert_eq!(info.check_borrow_mut_data().is_ok(), data.can_borrow_mut());
+
+            match op {
+                BorrowOp::Lamports => {
+                    let result = info.try_borrow_lamports();
+                    assert_eq!(result.is_ok(), lamports.can_borrow());
+                    if let Ok(value) = result {
+                        lamports.shared += 1;
+                        borrows.push(Borrow::Lamports(value));
+                    }
+                }
+                BorrowOp::LamportsMut => {
+                    let result = info.try_borrow_mut_lamports();
+                    assert_eq!(result.is_ok(), lamports.can_borrow_mut());
+                    if let Ok(value) = result {
+                        lamports.exclusive = true;
+                        borrows.push(Borrow::LamportsMut(value));
+                    }
+                }
+                BorrowOp::Data => {
+                    let result = info.try_borrow_data();
+                    assert_eq!(result.is_ok(), data.can_borrow());
+                    if let Ok(value) = result {
+                        data.shared += 1;
+                        borrows.push(Borrow::Data(value));
+                    }
+                }
+                BorrowOp::DataMut => {
+                    let result = info.try_borrow_mut_data();
+                    assert_eq!(result.is_ok(), data.can_borrow_mut());
+                    if let Ok(value) = result {
+                        data.exclusive = true;
+                        borrows.push(Borrow::DataMut(value));
+                    }
+                }
+                BorrowOp::Release(position) => {
+                    if borrows.is_empty() {
+                        continue;
+                    }
+                    // The references remain valid until they are dropped.
+                    match borrows.swap_remove(*position as usize % borrows.len()) {
+                        Borrow::Lamports(value) => {
+                            assert_eq!(*value, 1);
+                            lamports.shared -= 1;
+                        }
+                        Borrow::LamportsMut(value) => {
+                            assert_eq!(*value, 1);
+                            lamports.exclusive = false;
+                        }
+                        Borrow::Data(value) => {
+                            assert_eq!(&*value, &[1; 8]);
+                            data.shared -= 1;
+                        }
+                        Borrow::DataMut(value) => {
+                            assert_eq!(&*value, &[1; 8]);
+                            data.exclusive = false;
+                        }
+                    }
+                }
+            }
+        }
+
+        drop(borrows);
+
+        // All borrows are released once the references are dropped.
+        assert!(account.check_borrow_mut_lamports().is_ok());
+        assert!(account.check_borrow_mut_data().is_ok());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Runs `check` on models generated from pseudo-random bytes.
+    fn run<T: for<'a> Arbitrary<'a>>(iterations: usize, check: impl Fn(T)) {
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let mut bytes = std::vec![0u8; 4096];
+
+        for _ in 0..iterations {
+            for byte in bytes.iter_mut() {
+                // xorshift64
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                *byte = state as u8;
+            }
+
+            if let Ok(model) = T::arbitrary(&mut Unstructured::new(&bytes)) {
+                check(model);
+            }
+        }
+    }
+
+    #[test]
+    fn test_deserialize() {
+        run(64, |model: InputModel| {
+            model.check_deserialize::<MAX_TX_ACCOUNTS>();
+            model.check_deserialize::<3>();
+        });
+    }
+
+    #[test]
+    fn test_lazy() {
+        run(64, |model: InputModel| model.check_lazy());
+    }
+
+    #[test]
+    fn test_borrows() {
+        run(256, |model: BorrowModel| model.check());
+    }
+}
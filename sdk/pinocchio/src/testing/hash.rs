@@ -0,0 +1,151 @@
+//! Minimal SHA-256 implementation used to derive program addresses on host builds.
+
+/// Round constants.
+const K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// Initial hash values.
+const H: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+/// Incremental SHA-256 hasher.
+pub(crate) struct Sha256 {
+    /// Current hash state.
+    state: [u32; 8],
+
+    /// Pending bytes of the current block.
+    block: [u8; 64],
+
+    /// Number of pending bytes in the current block.
+    pending: usize,
+
+    /// Total number of bytes hashed.
+    length: u64,
+}
+
+impl Sha256 {
+    /// Creates a new hasher.
+    pub(crate) fn new() -> Self {
+        Self {
+            state: H,
+            block: [0; 64],
+            pending: 0,
+            length: 0,
+        }
+    }
+
+    /// Appends `bytes` to the hash input.
+    pub(crate) fn update(&mut self, bytes: &[u8]) {
+        self.length += bytes.len() as u64;
+
+        for byte in bytes {
+            self.block[self.pending] = *byte;
+            self.pending += 1;
+
+            if self.pending == 64 {
+                self.compress();
+                self.pending = 0;
+            }
+        }
+    }
+
+    /// Returns the hash of the input.
+    pub(crate) fn finalize(mut self) -> [u8; 32] {
+        let length = self.length * 8;
+
+        self.update(&[0x80]);
+        while self.pending != 56 {
+            self.update(&[0]);
+        }
+        self.update(&length.to_be_bytes());
+
+        let mut hash = [0; 32];
+        for (chunk, word) in hash.chunks_exact_mut(4).zip(self.state.iter()) {
+            chunk.copy_from_slice(&word.to_be_bytes());
+        }
+        hash
+    }
+
+    /// Processes the current block.
+    fn compress(&mut self) {
+        let mut w = [0u32; 64];
+
+        for (i, chunk) in self.block.chunks_exact(4).enumerate() {
+            w[i] = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        }
+
+        for i in 16..64 {
+            let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+            let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+            w[i] = w[i - 16]
+                .wrapping_add(s0)
+                .wrapping_add(w[i - 7])
+                .wrapping_add(s1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = self.state;
+
+        for i in 0..64 {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let ch = (e & f) ^ (!e & g);
+            let t1 = h
+                .wrapping_add(s1)
+                .wrapping_add(ch)
+                .wrapping_add(K[i])
+                .wrapping_add(w[i]);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let maj = (a & b) ^ (a & c) ^ (b & c);
+            let t2 = s0.wrapping_add(maj);
+
+            h = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(t1);
+            d = c;
+            c = b;
+            b = a;
+            a = t1.wrapping_add(t2);
+        }
+
+        for (state, value) in self.state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+            *state = state.wrapping_add(value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Sha256;
+
+    #[test]
+    fn test_sha256() {
+        let hash = Sha256::new().finalize();
+        assert_eq!(
+            hash[..4],
+            [0xe3, 0xb0, 0xc4, 0x42],
+            "hash of the empty input"
+        );
+
+        let mut hasher = Sha256::new();
+        hasher.update(b"abc");
+        assert_eq!(
+            hasher.finalize()[28..],
+            [0xf2, 0x00, 0x15, 0xad],
+            "hash of `abc`"
+        );
+
+        // Input spanning multiple blocks.
+        let mut hasher = Sha256::new();
+        hasher.update(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq");
+        assert_eq!(hasher.finalize()[..4], [0x24, 0x8d, 0x6a, 0x61]);
+    }
+}
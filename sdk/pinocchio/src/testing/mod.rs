@@ -0,0 +1,34 @@
+//! Helpers to exercise programs in native (host) builds.
+//!
+//! This module is only available when the `test-utils` feature is enabled. The
+//! types defined here reproduce the memory layout used by the SVM loaders, so
+//! code that relies on it &mdash; e.g., [`AccountInfo::realloc`], [`AccountInfo::close`]
+//! or the borrow tracking &mdash; behaves the same way as it does on-chain.
+//!
+//! Syscalls are routed to the [`SyscallStubs`] set with [`set_syscall_stubs`], which
+//! allows tests to intercept cross-program invocations, logs, return data and sysvars.
+//!
+//! The [`Executor`] builds on the stubs to run instructions end-to-end: it routes
+//! cross-program invocations to the registered programs, propagates signer and
+//! writable privileges and includes an emulation of the System program.
+//!
+//! When the `arbitrary` feature is enabled, the `fuzz` module provides models of the
+//! program input and account borrows to be used by fuzzers.
+//!
+//! [`AccountInfo::realloc`]: crate::account_info::AccountInfo::realloc
+//! [`AccountInfo::close`]: crate::account_info::AccountInfo::close
+
+mod account;
+mod executor;
+#[cfg(feature = "arbitrary")]
+pub mod fuzz;
+mod hash;
+mod serialize;
+mod stubs;
+mod system;
+
+pub use account::{AccountBuilder, TestAccount};
+pub use executor::{default_rent, Entrypoint, Executor, InstructionError, ProcessInstruction};
+pub use serialize::{serialize, AlignedBuffer};
+pub(crate) use stubs::syscall_stubs;
+pub use stubs::{set_syscall_stubs, Invocation, InvocationAccount, RecordingStubs, SyscallStubs};
@@ -0,0 +1,368 @@
+//! Serialization of the program input as done by the runtime.
+
+use core::mem::size_of;
+use std::vec::Vec;
+
+use crate::{
+    account_info::{Account, MAX_PERMITTED_DATA_INCREASE},
+    pubkey::Pubkey,
+    BPF_ALIGN_OF_U128, NON_DUP_MARKER,
+};
+
+use super::TestAccount;
+
+/// Value written as the rent epoch of serialized accounts.
+///
+/// Rent-exempt accounts have their rent epoch set to `u64::MAX`.
+const RENT_EXEMPT_RENT_EPOCH: u64 = u64::MAX;
+
+/// Serialize the input of a program instruction.
+///
+/// The input is serialized in the same format used by the runtime (aligned loader),
+/// which is the format expected by [`deserialize`] and [`InstructionContext`]:
+///
+/// - `u64`: number of accounts
+/// - for each account, either:
+///   - a non-duplicated account header, the account data, [`MAX_PERMITTED_DATA_INCREASE`]
+///     bytes of padding (aligned to 8 bytes) and the rent epoch; or
+///   - the index of the duplicated account followed by `7` bytes of padding.
+/// - `u64`: length of the instruction data
+/// - the instruction data
+/// - the program id
+///
+/// An account is serialized as a duplicate when it has the same key as an account that
+/// appears earlier on the `accounts` slice. In this case, the account is serialized as
+/// the index of the first account with that key.
+///
+/// [`deserialize`]: crate::entrypoint::deserialize
+/// [`InstructionContext`]: crate::entrypoint::InstructionContext
+///
+/// # Example
+///
+/// Calling a program entrypoint directly:
+///
+/// ```ignore
+/// use pinocchio::testing::{serialize, AccountBuilder};
+///
+/// let mut accounts = [AccountBuilder::new([1; 32]).lamports(100).writable(true).build()];
+/// let mut input = serialize(&PROGRAM_ID, &accounts, &[0]);
+///
+/// let result = unsafe { entrypoint(input.as_mut_ptr()) };
+/// assert_eq!(result, pinocchio::SUCCESS);
+///
+/// input.update_accounts(&mut accounts);
+/// ```
+pub fn serialize(
+    program_id: &Pubkey,
+    accounts: &[TestAccount],
+    instruction_data: &[u8],
+) -> AlignedBuffer {
+    let mut bytes = Vec::new();
+    // Offset of the account header of each account; duplicated accounts use the
+    // offset of the original account.
+    let mut offsets = Vec::with_capacity(accounts.len());
+
+    bytes.extend_from_slice(&(accounts.len() as u64).to_le_bytes());
+
+    for (index, account) in accounts.iter().enumerate() {
+        if let Some(original) = accounts[..index]
+            .iter()
+            .position(|a| a.key() == account.key())
+        {
+            assert!(
+                original < NON_DUP_MARKER as usize,
+                "duplicated account index out of range"
+            );
+
+            bytes.push(original as u8);
+            bytes.extend_from_slice(&[0; 7]);
+
+            offsets.push(offsets[original]);
+        } else {
+            offsets.push(bytes.len());
+
+            let header = unsafe { &*account.info().raw };
+
+            bytes.push(NON_DUP_MARKER);
+            bytes.push(header.is_signer);
+            bytes.push(header.is_writable);
+            bytes.push(header.executable);
+            // original data length (padding)
+            bytes.extend_from_slice(&[0; 4]);
+            bytes.extend_from_slice(&header.key);
+            bytes.extend_from_slice(&header.owner);
+            bytes.extend_from_slice(&header.lamports.to_le_bytes());
+            bytes.extend_from_slice(&header.data_len.to_le_bytes());
+            bytes.extend_from_slice(account.data());
+
+            bytes.resize(bytes.len() + MAX_PERMITTED_DATA_INCREASE, 0);
+            bytes.resize(bytes.len().next_multiple_of(BPF_ALIGN_OF_U128), 0);
+
+            bytes.extend_from_slice(&RENT_EXEMPT_RENT_EPOCH.to_le_bytes());
+        }
+    }
+
+    bytes.extend_from_slice(&(instruction_data.len() as u64).to_le_bytes());
+    bytes.extend_from_slice(instruction_data);
+    bytes.extend_from_slice(program_id);
+
+    AlignedBuffer::new(&bytes, offsets)
+}
+
+/// Serialized program input.
+///
+/// The buffer is aligned to `8` bytes, as the input buffer provided by the runtime,
+/// so it can be passed directly to a program `entrypoint` function.
+pub struct AlignedBuffer {
+    /// Memory of the buffer.
+    memory: Vec<u64>,
+
+    /// Length of the buffer in bytes.
+    len: usize,
+
+    /// Offset of the account header for each serialized account.
+    offsets: Vec<usize>,
+}
+
+impl AlignedBuffer {
+    /// Creates a new buffer by copying the serialized bytes.
+    fn new(bytes: &[u8], offsets: Vec<usize>) -> Self {
+        let mut memory = std::vec![0u64; bytes.len().div_ceil(size_of::<u64>())];
+
+        unsafe {
+            core::ptr::copy_nonoverlapping(
+                bytes.as_ptr(),
+                memory.as_mut_ptr() as *mut u8,
+                bytes.len(),
+            );
+        }
+
+        Self {
+            memory,
+            len: bytes.len(),
+            offsets,
+        }
+    }
+
+    /// Returns a mutable pointer to the start of the buffer.
+    ///
+    /// This is the value expected as the `input` of a program `entrypoint`.
+    #[inline(always)]
+    pub fn as_mut_ptr(&mut self) -> *mut u8 {
+        self.memory.as_mut_ptr() as *mut u8
+    }
+
+    /// Returns the serialized bytes.
+    pub fn as_slice(&self) -> &[u8] {
+        unsafe { core::slice::from_raw_parts(self.memory.as_ptr() as *const u8, self.len) }
+    }
+
+    /// Returns the length of the buffer in bytes.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Indicates whether the buffer is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of serialized accounts.
+    pub fn accounts_len(&self) -> usize {
+        self.offsets.len()
+    }
+
+    /// Returns the current lamports of the account at the given index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is out of bounds.
+    pub fn lamports(&self, index: usize) -> u64 {
+        self.header(index).lamports
+    }
+
+    /// Returns the current owner of the account at the given index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is out of bounds.
+    pub fn owner(&self, index: usize) -> &Pubkey {
+        &self.header(index).owner
+    }
+
+    /// Returns the current data of the account at the given index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is out of bounds.
+    pub fn data(&self, index: usize) -> &[u8] {
+        let offset = self.offsets[index] + size_of::<Account>();
+        &self.as_slice()[offset..offset + self.header(index).data_len as usize]
+    }
+
+    /// Copies the lamports, owner and data of the serialized accounts back to the
+    /// given accounts.
+    ///
+    /// This is used to observe the changes made by a program after its execution. The
+    /// `accounts` must be the same slice (or a copy of it) used to serialize the input.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of accounts does not match the number of serialized accounts.
+    pub fn update_accounts(&self, accounts: &mut [TestAccount]) {
+        assert_eq!(
+            accounts.len(),
+            self.offsets.len(),
+            "number of accounts does not match the serialized input"
+        );
+
+        for (index, account) in accounts.iter_mut().enumerate() {
+            let data = self.data(index);
+            assert!(
+                data.len() <= account.capacity(),
+                "account data exceeds the account capacity"
+            );
+
+            unsafe {
+                let raw = account.info().raw;
+                (*raw).owner = *self.owner(index);
+                (*raw).lamports = self.lamports(index);
+                (*raw).data_len = data.len() as u64;
+
+                core::ptr::copy_nonoverlapping(
+                    data.as_ptr(),
+                    (raw as *mut u8).add(size_of::<Account>()),
+                    data.len(),
+                );
+            }
+        }
+    }
+
+    /// Returns the header of the account at the given index.
+    fn header(&self, index: usize) -> &Account {
+        let offset = self.offsets[index];
+        unsafe { &*(self.as_slice().as_ptr().add(offset) as *const Account) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::mem::MaybeUninit;
+
+    use super::*;
+    use crate::{
+        account_info::AccountInfo,
+        entrypoint::{deserialize, InstructionContext, MaybeAccount},
+        testing::AccountBuilder,
+    };
+
+    fn accounts() -> [TestAccount; 4] {
+        [
+            AccountBuilder::new([1; 32])
+                .owner([9; 32])
+                .lamports(100)
+                .data(&[1, 2, 3])
+                .signer(true)
+                .writable(true)
+                .build(),
+            AccountBuilder::new([2; 32]).lamports(200).build(),
+            AccountBuilder::new([1; 32]).build(),
+            AccountBuilder::new([3; 32])
+                .lamports(300)
+                .space(16)
+                .writable(true)
+                .build(),
+        ]
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let accounts = accounts();
+        let mut input = serialize(&[7; 32], &accounts, &[4, 5, 6]);
+
+        const UNINIT: MaybeUninit<AccountInfo> = MaybeUninit::<AccountInfo>::uninit();
+        let mut infos = [UNINIT; 4];
+
+        let (program_id, count, instruction_data) =
+            unsafe { deserialize::<4>(input.as_mut_ptr(), &mut infos) };
+
+        assert_eq!(program_id, &[7; 32]);
+        assert_eq!(count, 4);
+        assert_eq!(instruction_data, &[4, 5, 6]);
+
+        let infos: &[AccountInfo] =
+            unsafe { core::slice::from_raw_parts(infos.as_ptr() as _, count) };
+
+        assert_eq!(infos[0].key(), &[1; 32]);
+        assert_eq!(infos[0].owner(), &[9; 32]);
+        assert_eq!(infos[0].lamports(), 100);
+        assert!(infos[0].is_signer());
+        assert!(infos[0].is_writable());
+        assert_eq!(&*infos[0].try_borrow_data().unwrap(), &[1, 2, 3]);
+
+        assert_eq!(infos[1].key(), &[2; 32]);
+        assert!(!infos[1].is_writable());
+
+        // duplicated account shares the same memory
+        assert!(infos[2] == infos[0]);
+
+        assert_eq!(infos[3].key(), &[3; 32]);
+        assert_eq!(infos[3].data_len(), 16);
+
+        // realloc uses the padding after the account data
+        infos[3]
+            .realloc(16 + MAX_PERMITTED_DATA_INCREASE, true)
+            .unwrap();
+        assert_eq!(input.data(3).len(), 16 + MAX_PERMITTED_DATA_INCREASE);
+    }
+
+    #[test]
+    fn test_lazy_context() {
+        let accounts = accounts();
+        let mut input = serialize(&[7; 32], &accounts, &[4, 5, 6]);
+
+        let mut context = InstructionContext::new(input.as_mut_ptr());
+        assert_eq!(context.available(), 4);
+
+        for (index, expected) in accounts.iter().enumerate() {
+            match context.next_account().unwrap() {
+                MaybeAccount::Account(account) => assert_eq!(account.key(), expected.key()),
+                MaybeAccount::Duplicated(original) => {
+                    assert_eq!(index, 2);
+                    assert_eq!(original, 0);
+                }
+            }
+        }
+
+        assert_eq!(context.instruction_data().unwrap(), &[4, 5, 6]);
+        assert_eq!(context.program_id().unwrap(), &[7; 32]);
+    }
+
+    #[test]
+    fn test_update_accounts() {
+        let mut accounts = accounts();
+        let mut input = serialize(&[7; 32], &accounts, &[]);
+
+        const UNINIT: MaybeUninit<AccountInfo> = MaybeUninit::<AccountInfo>::uninit();
+        let mut infos = [UNINIT; 4];
+
+        let (_, count, _) = unsafe { deserialize::<4>(input.as_mut_ptr(), &mut infos) };
+        let infos: &[AccountInfo] =
+            unsafe { core::slice::from_raw_parts(infos.as_ptr() as _, count) };
+
+        *infos[0].try_borrow_mut_lamports().unwrap() -= 50;
+        *infos[3].try_borrow_mut_lamports().unwrap() += 50;
+        infos[0].assign(&[8; 32]);
+        infos[3].realloc(20, true).unwrap();
+        infos[3].try_borrow_mut_data().unwrap()[19] = 1;
+
+        input.update_accounts(&mut accounts);
+
+        assert_eq!(accounts[0].lamports(), 50);
+        assert_eq!(accounts[0].owner(), &[8; 32]);
+        assert_eq!(accounts[2].lamports(), 50);
+        assert_eq!(accounts[3].lamports(), 350);
+        assert_eq!(accounts[3].data_len(), 20);
+        assert_eq!(accounts[3].data()[19], 1);
+    }
+}
@@ -0,0 +1,750 @@
+//! Pluggable implementation of the syscalls for host builds.
+
+use core::cell::{Cell, RefCell};
+use std::{
+    format,
+    rc::Rc,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use crate::{
+    curve::is_on_curve,
+    instruction::{Account, Instruction, Signer},
+    program::ReturnValue,
+    program_error::{ProgramError, UNSUPPORTED_SYSVAR},
+    pubkey::{Pubkey, MAX_SEEDS, MAX_SEED_LEN},
+    sysvars::{clock::Clock, fees::Fees, rent::Rent},
+    ProgramResult, SUCCESS,
+};
+
+use super::hash::Sha256;
+
+/// Marker appended to the seeds when deriving a program address.
+const PDA_MARKER: &[u8; 21] = b"ProgramDerivedAddress";
+
+/// Default compute unit limit of an instruction.
+const DEFAULT_COMPUTE_UNITS: u64 = 1_400_000;
+
+std::thread_local! {
+    /// Syscall stubs of the current thread.
+    static SYSCALL_STUBS: RefCell<Rc<dyn SyscallStubs>> =
+        RefCell::new(Rc::new(RecordingStubs::default()));
+}
+
+/// Sets the syscall stubs used by the current thread, returning the previous ones.
+///
+/// Stubs are installed per thread, so tests running in parallel do not interfere
+/// with each other. Until stubs are set, a [`RecordingStubs`] is used.
+pub fn set_syscall_stubs(stubs: Rc<dyn SyscallStubs>) -> Rc<dyn SyscallStubs> {
+    SYSCALL_STUBS.with(|current| current.replace(stubs))
+}
+
+/// Returns the syscall stubs used by the current thread.
+///
+/// The stubs are cloned out of the thread local storage, so stubs are free to
+/// call syscalls themselves (e.g., when executing a cross-program invocation).
+pub(crate) fn syscall_stubs() -> Rc<dyn SyscallStubs> {
+    SYSCALL_STUBS.with(|current| current.borrow().clone())
+}
+
+/// Implementation of the syscalls for host builds.
+///
+/// On non-solana targets, every syscall made through pinocchio is routed to the
+/// stubs installed with [`set_syscall_stubs`]. Each method has a default
+/// implementation, so implementors only need to override the syscalls relevant
+/// to their tests.
+pub trait SyscallStubs {
+    /// Log a message.
+    fn sol_log(&self, message: &str) {
+        std::println!("{message}");
+    }
+
+    /// Log 64-bit values represented as hexadecimal.
+    fn sol_log_64(&self, arg1: u64, arg2: u64, arg3: u64, arg4: u64, arg5: u64) {
+        self.sol_log(&format!(
+            "{arg1:#x}, {arg2:#x}, {arg3:#x}, {arg4:#x}, {arg5:#x}"
+        ));
+    }
+
+    /// Log a `Pubkey`.
+    fn sol_log_pubkey(&self, pubkey: &Pubkey) {
+        self.sol_log(&format!("{pubkey:?}"));
+    }
+
+    /// Log slices of data.
+    fn sol_log_data(&self, data: &[&[u8]]) {
+        self.sol_log(&format!("data: {data:?}"));
+    }
+
+    /// Log the remaining compute units.
+    fn sol_log_compute_units(&self) {
+        self.sol_log("SyscallStubs: sol_log_compute_units() not available");
+    }
+
+    /// Invoke a cross-program instruction.
+    ///
+    /// The `accounts` are in the same order as the `accounts` of the `instruction`.
+    ///
+    /// A failed invocation aborts the calling program on-chain, so the program
+    /// never observes the error. Since unwinding out of an `extern "C"` entrypoint
+    /// is not possible either, implementations record a failure instead, to be
+    /// reported once the program returns (see [`RecordingStubs::invoke_error`]).
+    fn sol_invoke_signed(
+        &self,
+        _instruction: &Instruction,
+        _accounts: &[Account],
+        _signers: &[Signer],
+    ) {
+        self.sol_log("SyscallStubs: sol_invoke_signed() not available");
+    }
+
+    /// Set the return data of the running program.
+    fn sol_set_return_data(&self, _data: &[u8]) {}
+
+    /// Get the return data and the program that set it.
+    fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        None
+    }
+
+    /// Write the `Clock` sysvar to `var_addr`.
+    ///
+    /// # Safety
+    ///
+    /// `var_addr` must be valid for writes of a `Clock`.
+    unsafe fn sol_get_clock_sysvar(&self, _var_addr: *mut u8) -> u64 {
+        UNSUPPORTED_SYSVAR
+    }
+
+    /// Write the `EpochSchedule` sysvar to `var_addr`.
+    ///
+    /// # Safety
+    ///
+    /// `var_addr` must be valid for writes of an `EpochSchedule`.
+    unsafe fn sol_get_epoch_schedule_sysvar(&self, _var_addr: *mut u8) -> u64 {
+        UNSUPPORTED_SYSVAR
+    }
+
+    /// Write the `Fees` sysvar to `var_addr`.
+    ///
+    /// # Safety
+    ///
+    /// `var_addr` must be valid for writes of a `Fees`.
+    unsafe fn sol_get_fees_sysvar(&self, _var_addr: *mut u8) -> u64 {
+        UNSUPPORTED_SYSVAR
+    }
+
+    /// Write the `Rent` sysvar to `var_addr`.
+    ///
+    /// # Safety
+    ///
+    /// `var_addr` must be valid for writes of a `Rent`.
+    unsafe fn sol_get_rent_sysvar(&self, _var_addr: *mut u8) -> u64 {
+        UNSUPPORTED_SYSVAR
+    }
+
+    /// Write the `LastRestartSlot` sysvar to `var_addr`.
+    ///
+    /// # Safety
+    ///
+    /// `var_addr` must be valid for writes of a `LastRestartSlot`.
+    unsafe fn sol_get_last_restart_slot(&self, _var_addr: *mut u8) -> u64 {
+        UNSUPPORTED_SYSVAR
+    }
+
+    /// Write the `EpochRewards` sysvar to `var_addr`.
+    ///
+    /// # Safety
+    ///
+    /// `var_addr` must be valid for writes of an `EpochRewards`.
+    unsafe fn sol_get_epoch_rewards_sysvar(&self, _var_addr: *mut u8) -> u64 {
+        UNSUPPORTED_SYSVAR
+    }
+
+    /// Create a program derived address.
+    ///
+    /// The default implementation derives the address in the same way as the runtime.
+    fn sol_create_program_address(
+        &self,
+        seeds: &[&[u8]],
+        program_id: &Pubkey,
+    ) -> Result<Pubkey, ProgramError> {
+        create_program_address(seeds, program_id)
+    }
+
+    /// Find a valid program derived address and its bump seed.
+    ///
+    /// The default implementation derives the address in the same way as the runtime.
+    fn sol_try_find_program_address(
+        &self,
+        seeds: &[&[u8]],
+        program_id: &Pubkey,
+    ) -> Option<(Pubkey, u8)> {
+        try_find_program_address(seeds, program_id)
+    }
+
+    /// Like C `memcpy`.
+    ///
+    /// # Safety
+    ///
+    /// The same safety rules apply as in [`core::ptr::copy_nonoverlapping`].
+    unsafe fn sol_memcpy(&self, dst: *mut u8, src: *const u8, n: usize) {
+        core::ptr::copy_nonoverlapping(src, dst, n);
+    }
+
+    /// Like C `memmove`.
+    ///
+    /// # Safety
+    ///
+    /// The same safety rules apply as in [`core::ptr::copy`].
+    unsafe fn sol_memmove(&self, dst: *mut u8, src: *const u8, n: usize) {
+        core::ptr::copy(src, dst, n);
+    }
+
+    /// Like C `memcmp`.
+    ///
+    /// # Safety
+    ///
+    /// `s1` and `s2` must be valid for reads of `n` bytes.
+    unsafe fn sol_memcmp(&self, s1: *const u8, s2: *const u8, n: usize) -> i32 {
+        let s1 = core::slice::from_raw_parts(s1, n);
+        let s2 = core::slice::from_raw_parts(s2, n);
+
+        s1.iter()
+            .zip(s2)
+            .find(|(a, b)| a != b)
+            .map(|(a, b)| *a as i32 - *b as i32)
+            .unwrap_or(0)
+    }
+
+    /// Like C `memset`.
+    ///
+    /// # Safety
+    ///
+    /// The same safety rules apply as in [`core::ptr::write_bytes`].
+    unsafe fn sol_memset(&self, s: *mut u8, c: u8, n: usize) {
+        core::ptr::write_bytes(s, c, n);
+    }
+}
+
+/// Create a program derived address in the same way as the runtime.
+fn create_program_address(seeds: &[&[u8]], program_id: &Pubkey) -> Result<Pubkey, ProgramError> {
+    if seeds.len() > MAX_SEEDS || seeds.iter().any(|seed| seed.len() > MAX_SEED_LEN) {
+        return Err(ProgramError::MaxSeedLengthExceeded);
+    }
+
+    let mut hasher = Sha256::new();
+    for seed in seeds {
+        hasher.update(seed);
+    }
+    hasher.update(program_id);
+    hasher.update(PDA_MARKER);
+
+    let address = hasher.finalize();
+
+    if is_on_curve(&address) {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    Ok(address)
+}
+
+/// Find a program derived address in the same way as the runtime.
+fn try_find_program_address(seeds: &[&[u8]], program_id: &Pubkey) -> Option<(Pubkey, u8)> {
+    // One seed is reserved for the bump.
+    if seeds.len() >= MAX_SEEDS {
+        return None;
+    }
+
+    for bump in (0..=u8::MAX).rev() {
+        let bump_seed = [bump];
+        let mut seeds_with_bump = seeds.to_vec();
+        seeds_with_bump.push(&bump_seed);
+
+        match create_program_address(&seeds_with_bump, program_id) {
+            Ok(address) => return Some((address, bump)),
+            Err(ProgramError::InvalidSeeds) => (),
+            Err(_) => return None,
+        }
+    }
+
+    None
+}
+
+/// A cross-program invocation recorded by [`RecordingStubs`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Invocation {
+    /// Program invoked.
+    pub program_id: Pubkey,
+
+    /// Accounts of the instruction.
+    pub accounts: Vec<InvocationAccount>,
+
+    /// Data of the instruction.
+    pub data: Vec<u8>,
+
+    /// Seeds of each signer.
+    pub signers: Vec<Vec<Vec<u8>>>,
+}
+
+/// An account of a recorded [`Invocation`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InvocationAccount {
+    /// Public key of the account.
+    pub pubkey: Pubkey,
+
+    /// Indicates whether the account is writable or not.
+    pub is_writable: bool,
+
+    /// Indicates whether the account signed the instruction or not.
+    pub is_signer: bool,
+}
+
+/// Syscall stubs that record calls for later assertions.
+///
+/// This is the implementation used when no stubs have been set. Logs,
+/// cross-program invocations and return data are recorded. The `Clock`, `Fees`
+/// and `Rent` sysvars return the values set with [`RecordingStubs::set_clock`],
+/// [`RecordingStubs::set_fees`] and [`RecordingStubs::set_rent`], and are
+/// unsupported until set; the other sysvars are always unsupported.
+///
+/// Invocations succeed unless an error is set with
+/// [`RecordingStubs::set_invoke_result`], in which case the failure is recorded
+/// and returned by [`RecordingStubs::invoke_error`].
+///
+/// # Example
+///
+/// ```
+/// use pinocchio::{msg, testing::RecordingStubs};
+///
+/// let stubs = RecordingStubs::install();
+///
+/// msg!("hello");
+///
+/// assert_eq!(stubs.logs(), ["hello"]);
+/// ```
+pub struct RecordingStubs {
+    /// Logged messages.
+    logs: RefCell<Vec<String>>,
+
+    /// Logged data slices.
+    data_logs: RefCell<Vec<Vec<Vec<u8>>>>,
+
+    /// Cross-program invocations.
+    invocations: RefCell<Vec<Invocation>>,
+
+    /// Result of cross-program invocations.
+    invoke_result: RefCell<ProgramResult>,
+
+    /// Error of the first failed cross-program invocation.
+    invoke_error: RefCell<Option<ProgramError>>,
+
+    /// Current return data and the program that set it.
+    return_data: RefCell<Option<(Pubkey, Vec<u8>)>>,
+
+    /// Program id used when setting the return data.
+    program_id: Cell<Pubkey>,
+
+    /// Remaining compute units reported by `sol_log_compute_units`.
+    compute_units: Cell<u64>,
+
+    /// Value of the `Clock` sysvar.
+    clock: Cell<Option<Clock>>,
+
+    /// Value of the `Fees` sysvar.
+    fees: RefCell<Option<Fees>>,
+
+    /// Value of the `Rent` sysvar.
+    rent: RefCell<Option<Rent>>,
+}
+
+impl Default for RecordingStubs {
+    fn default() -> Self {
+        Self {
+            logs: RefCell::default(),
+            data_logs: RefCell::default(),
+            invocations: RefCell::default(),
+            invoke_result: RefCell::new(Ok(())),
+            invoke_error: RefCell::default(),
+            return_data: RefCell::default(),
+            program_id: Cell::default(),
+            compute_units: Cell::new(DEFAULT_COMPUTE_UNITS),
+            clock: Cell::default(),
+            fees: RefCell::default(),
+            rent: RefCell::default(),
+        }
+    }
+}
+
+impl RecordingStubs {
+    /// Creates new stubs and sets them as the stubs of the current thread.
+    pub fn install() -> Rc<Self> {
+        let stubs = Rc::new(Self::default());
+        set_syscall_stubs(stubs.clone());
+        stubs
+    }
+
+    /// Returns the logged messages.
+    ///
+    /// Values logged with `sol_log_64` and `sol_log_pubkey` are recorded as their
+    /// formatted string.
+    pub fn logs(&self) -> Vec<String> {
+        self.logs.borrow().clone()
+    }
+
+    /// Returns the data slices logged with `sol_log_data`.
+    pub fn data_logs(&self) -> Vec<Vec<Vec<u8>>> {
+        self.data_logs.borrow().clone()
+    }
+
+    /// Returns the cross-program invocations.
+    pub fn invocations(&self) -> Vec<Invocation> {
+        self.invocations.borrow().clone()
+    }
+
+    /// Returns the error of the first failed cross-program invocation.
+    ///
+    /// On-chain, the program would have been aborted by that invocation.
+    pub fn invoke_error(&self) -> Option<ProgramError> {
+        self.invoke_error.borrow().clone()
+    }
+
+    /// Returns the current return data and the program that set it.
+    pub fn return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        self.return_data.borrow().clone()
+    }
+
+    /// Returns the current return data as a value of type `T`.
+    ///
+    /// Returns `None` when there is no return data or its length does not match
+    /// the size of `T`.
+    pub fn return_value<T: ReturnValue>(&self) -> Option<T> {
+        self.return_data
+            .borrow()
+            .as_ref()
+            .and_then(|(_, data)| T::from_bytes(data))
+    }
+
+    /// Clears the recorded logs, invocations, invocation error and return data.
+    pub fn clear(&self) {
+        self.logs.borrow_mut().clear();
+        self.data_logs.borrow_mut().clear();
+        self.invocations.borrow_mut().clear();
+        self.invoke_error.borrow_mut().take();
+        self.return_data.borrow_mut().take();
+    }
+
+    /// Clears the return data.
+    pub(crate) fn clear_return_data(&self) {
+        self.return_data.borrow_mut().take();
+    }
+
+    /// Sets the program id recorded when the return data is set.
+    pub fn set_program_id(&self, program_id: Pubkey) {
+        self.program_id.set(program_id);
+    }
+
+    /// Sets the result of the following cross-program invocations.
+    pub fn set_invoke_result(&self, result: ProgramResult) {
+        *self.invoke_result.borrow_mut() = result;
+    }
+
+    /// Sets the remaining compute units.
+    pub fn set_compute_units(&self, compute_units: u64) {
+        self.compute_units.set(compute_units);
+    }
+
+    /// Sets the value of the `Clock` sysvar.
+    pub fn set_clock(&self, clock: Clock) {
+        self.clock.set(Some(clock));
+    }
+
+    /// Sets the value of the `Fees` sysvar.
+    pub fn set_fees(&self, fees: Fees) {
+        self.fees.replace(Some(fees));
+    }
+
+    /// Sets the value of the `Rent` sysvar.
+    pub fn set_rent(&self, rent: Rent) {
+        self.rent.replace(Some(rent));
+    }
+}
+
+impl SyscallStubs for RecordingStubs {
+    fn sol_log(&self, message: &str) {
+        self.logs.borrow_mut().push(message.to_string());
+    }
+
+    fn sol_log_data(&self, data: &[&[u8]]) {
+        self.data_logs
+            .borrow_mut()
+            .push(data.iter().map(|slice| slice.to_vec()).collect());
+    }
+
+    fn sol_log_compute_units(&self) {
+        self.sol_log(&format!(
+            "Program consumption: {} units remaining",
+            self.compute_units.get()
+        ));
+    }
+
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        _accounts: &[Account],
+        signers: &[Signer],
+    ) {
+        let signers = signers
+            .iter()
+            .map(|signer| {
+                let seeds =
+                    unsafe { core::slice::from_raw_parts(signer.seeds, signer.len as usize) };
+                seeds.iter().map(|seed| seed.to_vec()).collect()
+            })
+            .collect();
+
+        self.invocations.borrow_mut().push(Invocation {
+            program_id: *instruction.program_id,
+            accounts: instruction
+                .accounts
+                .iter()
+                .map(|meta| InvocationAccount {
+                    pubkey: *meta.pubkey,
+                    is_writable: meta.is_writable,
+                    is_signer: meta.is_signer,
+                })
+                .collect(),
+            data: instruction.data.to_vec(),
+            signers,
+        });
+
+        if let Err(error) = self.invoke_result.borrow().as_ref() {
+            self.invoke_error.borrow_mut().get_or_insert(error.clone());
+        }
+    }
+
+    fn sol_set_return_data(&self, data: &[u8]) {
+        self.return_data
+            .replace(Some((self.program_id.get(), data.to_vec())));
+    }
+
+    fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        self.return_data()
+    }
+
+    unsafe fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        match self.clock.get() {
+            Some(clock) => {
+                (var_addr as *mut Clock).write(clock);
+                SUCCESS
+            }
+            None => UNSUPPORTED_SYSVAR,
+        }
+    }
+
+    unsafe fn sol_get_fees_sysvar(&self, var_addr: *mut u8) -> u64 {
+        match self.fees.borrow().as_ref() {
+            Some(fees) => {
+                (var_addr as *mut Fees).write(fees.clone());
+                SUCCESS
+            }
+            None => UNSUPPORTED_SYSVAR,
+        }
+    }
+
+    unsafe fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        match self.rent.borrow().as_ref() {
+            Some(rent) => {
+                (var_addr as *mut Rent).write(rent.clone());
+                SUCCESS
+            }
+            None => UNSUPPORTED_SYSVAR,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        instruction::{AccountMeta, Seed},
+        log::{sol_log_64, sol_log_compute_units, sol_log_data},
+        memory::{sol_memcmp, sol_memcpy, sol_memset},
+        msg,
+        program::{get_return_data, invoke_signed, set_return_data, set_return_value},
+        pubkey::{create_program_address, find_program_address},
+        sysvars::Sysvar,
+        testing::AccountBuilder,
+    };
+
+    #[test]
+    fn test_logs() {
+        let stubs = RecordingStubs::install();
+
+        msg!("hello");
+        sol_log_64(1, 2, 3, 4, 255);
+        sol_log_data(&[&[1, 2], &[3]]);
+        stubs.set_compute_units(100);
+        sol_log_compute_units();
+
+        assert_eq!(
+            stubs.logs(),
+            [
+                "hello",
+                "0x1, 0x2, 0x3, 0x4, 0xff",
+                "Program consumption: 100 units remaining"
+            ]
+        );
+        assert_eq!(stubs.data_logs(), [[std::vec![1, 2], std::vec![3]]]);
+
+        stubs.clear();
+        assert!(stubs.logs().is_empty());
+    }
+
+    #[test]
+    fn test_invoke() {
+        let stubs = RecordingStubs::install();
+
+        let from = AccountBuilder::new([1; 32])
+            .signer(true)
+            .writable(true)
+            .build();
+        let to = AccountBuilder::new([2; 32]).writable(true).build();
+
+        let accounts = [
+            AccountMeta::writable_signer(&[1; 32]),
+            AccountMeta::writable(&[2; 32]),
+        ];
+        let instruction = Instruction {
+            program_id: &[0; 32],
+            accounts: &accounts,
+            data: &[2, 0, 0, 0],
+        };
+        let seeds = [Seed::from(b"seed"), Seed::from(&[255])];
+
+        invoke_signed(&instruction, &[&from, &to], &[Signer::from(&seeds)]).unwrap();
+
+        assert_eq!(
+            stubs.invocations(),
+            [Invocation {
+                program_id: [0; 32],
+                accounts: std::vec![
+                    InvocationAccount {
+                        pubkey: [1; 32],
+                        is_writable: true,
+                        is_signer: true,
+                    },
+                    InvocationAccount {
+                        pubkey: [2; 32],
+                        is_writable: true,
+                        is_signer: false,
+                    },
+                ],
+                data: std::vec![2, 0, 0, 0],
+                signers: std::vec![std::vec![b"seed".to_vec(), std::vec![255]]],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_failed_invoke() {
+        let stubs = RecordingStubs::install();
+
+        let account = AccountBuilder::new([1; 32]).build();
+        let accounts = [AccountMeta::readonly(&[1; 32])];
+        let instruction = Instruction {
+            program_id: &[0; 32],
+            accounts: &accounts,
+            data: &[],
+        };
+
+        // the failure is recorded, as the program would be aborted on-chain
+        stubs.set_invoke_result(Err(ProgramError::Custom(1)));
+        invoke_signed(&instruction, &[&account], &[]).unwrap();
+        stubs.set_invoke_result(Err(ProgramError::Custom(2)));
+        invoke_signed(&instruction, &[&account], &[]).unwrap();
+
+        assert_eq!(stubs.invocations().len(), 2);
+        assert_eq!(stubs.invoke_error(), Some(ProgramError::Custom(1)));
+
+        stubs.clear();
+        assert_eq!(stubs.invoke_error(), None);
+    }
+
+    #[test]
+    fn test_return_data() {
+        let stubs = RecordingStubs::install();
+        assert!(get_return_data().is_none());
+
+        stubs.set_program_id([5; 32]);
+        set_return_data(&[1, 2, 3]);
+
+        let return_data = get_return_data().unwrap();
+        assert_eq!(return_data.program_id(), &[5; 32]);
+        assert_eq!(return_data.as_slice(), &[1, 2, 3]);
+    }
+
+    #[test]
+    fn test_return_value() {
+        let stubs = RecordingStubs::install();
+        assert_eq!(stubs.return_value::<u64>(), None);
+
+        set_return_value(&42u64);
+        assert_eq!(stubs.return_value::<u64>(), Some(42));
+        assert_eq!(stubs.return_value::<u32>(), None);
+
+        let return_data = get_return_data().unwrap();
+        assert_eq!(return_data.value::<u64>(), Some(42));
+        assert_eq!(return_data.value::<[u8; 8]>(), Some(42u64.to_le_bytes()));
+    }
+
+    #[test]
+    fn test_sysvars() {
+        let stubs = RecordingStubs::install();
+        assert_eq!(Clock::get().err(), Some(ProgramError::UnsupportedSysvar));
+
+        stubs.set_clock(Clock {
+            slot: 10,
+            ..Default::default()
+        });
+        assert_eq!(Clock::get().unwrap().slot, 10);
+    }
+
+    #[test]
+    fn test_program_address() {
+        // Reference values computed with the runtime derivation.
+        let (address, bump) = find_program_address(&[b"hello"], &[1; 32]);
+        assert_eq!(bump, 253);
+        assert_eq!(address[..4], [177, 214, 39, 110]);
+
+        assert_eq!(
+            create_program_address(&[b"hello", &[253]], &[1; 32]),
+            Ok(address)
+        );
+        // Bump 255 derives an address on the curve.
+        assert_eq!(
+            create_program_address(&[b"hello", &[255]], &[1; 32]),
+            Err(ProgramError::InvalidSeeds)
+        );
+        assert_eq!(
+            create_program_address(&[&[0; MAX_SEED_LEN + 1]], &[1; 32]),
+            Err(ProgramError::MaxSeedLengthExceeded)
+        );
+    }
+
+    #[test]
+    fn test_memory() {
+        let mut dst = [0u8; 4];
+
+        unsafe {
+            sol_memcpy(&mut dst, &[1, 2, 3, 4], 4);
+            assert_eq!(dst, [1, 2, 3, 4]);
+
+            assert_eq!(sol_memcmp(&dst, &[1, 2, 3, 4], 4), 0);
+            assert!(sol_memcmp(&dst, &[1, 2, 4, 4], 4) < 0);
+
+            sol_memset(&mut dst, 9, 2);
+            assert_eq!(dst, [9, 9, 3, 4]);
+        }
+    }
+}
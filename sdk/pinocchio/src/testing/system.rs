@@ -0,0 +1,880 @@
+//! Emulation of the System program.
+
+use crate::{
+    program_error::ProgramError,
+    pubkey::{Pubkey, MAX_SEED_LEN},
+    sysvars::{rent::Rent, Sysvar},
+    ProgramResult,
+};
+
+use super::{hash::Sha256, TestAccount};
+
+/// Address of the System program.
+pub(crate) const ID: Pubkey = [0; 32];
+
+/// Maximum length of the data of an account.
+const MAX_PERMITTED_DATA_LENGTH: u64 = 10 * 1024 * 1024;
+
+/// Size of the state of a nonce account.
+const NONCE_STATE_SIZE: usize = 80;
+
+/// Prefix used to derive a durable nonce from a blockhash.
+const DURABLE_NONCE_HASH_PREFIX: &[u8] = b"DURABLE_NONCE";
+
+/// Marker used by program derived addresses, which cannot own accounts.
+const PDA_MARKER: &[u8] = b"ProgramDerivedAddress";
+
+/// Errors of the System program, reported as custom program errors.
+#[repr(u32)]
+#[derive(Clone, Copy)]
+enum SystemError {
+    AccountAlreadyInUse = 0,
+    ResultWithNegativeLamports = 1,
+    InvalidAccountDataLength = 3,
+    MaxSeedLengthExceeded = 4,
+    AddressWithSeedMismatch = 5,
+    NonceNoRecentBlockhashes = 6,
+    NonceBlockhashNotExpired = 7,
+}
+
+impl From<SystemError> for ProgramError {
+    fn from(error: SystemError) -> Self {
+        ProgramError::Custom(error as u32)
+    }
+}
+
+/// Processes a System program instruction.
+///
+/// The behaviour follows the native System program, including the errors returned.
+pub(crate) fn process_instruction(
+    accounts: &mut [TestAccount],
+    indices: &[usize],
+    data: &[u8],
+) -> ProgramResult {
+    let mut data = Reader(data);
+    let signers = accounts
+        .iter()
+        .filter(|account| account.is_signer())
+        .map(|account| *account.key())
+        .collect::<std::vec::Vec<_>>();
+    let index = |position: usize| {
+        indices
+            .get(position)
+            .copied()
+            .ok_or(ProgramError::NotEnoughAccountKeys)
+    };
+
+    match data.u32()? {
+        // CreateAccount
+        0 => {
+            let lamports = data.u64()?;
+            let space = data.u64()?;
+            let owner = data.pubkey()?;
+            let (from, to) = (index(0)?, index(1)?);
+            let address = *accounts[to].key();
+
+            create_account(
+                accounts, from, to, &address, lamports, space, &owner, &signers,
+            )
+        }
+        // Assign
+        1 => {
+            let owner = data.pubkey()?;
+            let account = index(0)?;
+            let address = *accounts[account].key();
+
+            assign(&mut accounts[account], &address, &owner, &signers)
+        }
+        // Transfer
+        2 => {
+            let lamports = data.u64()?;
+            let (from, to) = (index(0)?, index(1)?);
+
+            if !accounts[from].is_signer() {
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+
+            transfer(accounts, from, to, lamports)
+        }
+        // CreateAccountWithSeed
+        3 => {
+            let base = data.pubkey()?;
+            let seed = data.string()?;
+            let lamports = data.u64()?;
+            let space = data.u64()?;
+            let owner = data.pubkey()?;
+            let (from, to) = (index(0)?, index(1)?);
+            let address = address_with_seed(accounts[to].key(), &base, seed, &owner)?;
+
+            create_account(
+                accounts, from, to, &address, lamports, space, &owner, &signers,
+            )
+        }
+        // AdvanceNonceAccount
+        4 => {
+            let (nonce, recent_blockhashes) = (index(0)?, index(1)?);
+            let (blockhash, lamports_per_signature) =
+                recent_blockhash(&accounts[recent_blockhashes])?;
+            let nonce = &mut accounts[nonce];
+
+            if !nonce.is_writable() {
+                return Err(ProgramError::InvalidArgument);
+            }
+
+            match NonceState::read(nonce)? {
+                NonceState::Initialized {
+                    authority,
+                    durable_nonce,
+                    ..
+                } => {
+                    if !signers.contains(&authority) {
+                        return Err(ProgramError::MissingRequiredSignature);
+                    }
+
+                    let next_durable_nonce = durable_nonce_from(&blockhash);
+                    if next_durable_nonce == durable_nonce {
+                        return Err(SystemError::NonceBlockhashNotExpired.into());
+                    }
+
+                    NonceState::Initialized {
+                        authority,
+                        durable_nonce: next_durable_nonce,
+                        lamports_per_signature,
+                    }
+                    .write(nonce);
+
+                    Ok(())
+                }
+                _ => Err(ProgramError::InvalidAccountData),
+            }
+        }
+        // WithdrawNonceAccount
+        5 => {
+            let lamports = data.u64()?;
+            let (from, to, recent_blockhashes) = (index(0)?, index(1)?, index(2)?);
+            let _rent_sysvar = index(3)?;
+
+            if !accounts[from].is_writable() {
+                return Err(ProgramError::InvalidArgument);
+            }
+
+            let signer = match NonceState::read(&accounts[from])? {
+                NonceState::Uninitialized => {
+                    if lamports > accounts[from].lamports() {
+                        return Err(ProgramError::InsufficientFunds);
+                    }
+                    *accounts[from].key()
+                }
+                NonceState::Initialized {
+                    authority,
+                    durable_nonce,
+                    ..
+                } => {
+                    if lamports == accounts[from].lamports() {
+                        let (blockhash, _) = recent_blockhash(&accounts[recent_blockhashes])?;
+                        if durable_nonce_from(&blockhash) == durable_nonce {
+                            return Err(SystemError::NonceBlockhashNotExpired.into());
+                        }
+                        NonceState::Uninitialized.write(&mut accounts[from]);
+                    } else {
+                        let minimum_balance =
+                            Rent::get()?.minimum_balance(accounts[from].data_len());
+                        if lamports.saturating_add(minimum_balance) > accounts[from].lamports() {
+                            return Err(ProgramError::InsufficientFunds);
+                        }
+                    }
+                    authority
+                }
+            };
+
+            if !signers.contains(&signer) {
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+
+            move_lamports(accounts, from, to, lamports)
+        }
+        // InitializeNonceAccount
+        6 => {
+            let authority = data.pubkey()?;
+            let (nonce, recent_blockhashes) = (index(0)?, index(1)?);
+            let _rent_sysvar = index(2)?;
+
+            if !accounts[nonce].is_writable() {
+                return Err(ProgramError::InvalidArgument);
+            }
+
+            match NonceState::read(&accounts[nonce])? {
+                NonceState::Uninitialized => {
+                    let minimum_balance = Rent::get()?.minimum_balance(accounts[nonce].data_len());
+                    if accounts[nonce].lamports() < minimum_balance {
+                        return Err(ProgramError::InsufficientFunds);
+                    }
+
+                    let (blockhash, lamports_per_signature) =
+                        recent_blockhash(&accounts[recent_blockhashes])?;
+
+                    NonceState::Initialized {
+                        authority,
+                        durable_nonce: durable_nonce_from(&blockhash),
+                        lamports_per_signature,
+                    }
+                    .write(&mut accounts[nonce]);
+
+                    Ok(())
+                }
+                NonceState::Initialized { .. } => Err(ProgramError::InvalidAccountData),
+            }
+        }
+        // AuthorizeNonceAccount
+        7 => {
+            let new_authority = data.pubkey()?;
+            let nonce = &mut accounts[index(0)?];
+
+            if !nonce.is_writable() {
+                return Err(ProgramError::InvalidArgument);
+            }
+
+            match NonceState::read(nonce)? {
+                NonceState::Initialized {
+                    authority,
+                    durable_nonce,
+                    lamports_per_signature,
+                } => {
+                    if !signers.contains(&authority) {
+                        return Err(ProgramError::MissingRequiredSignature);
+                    }
+
+                    NonceState::Initialized {
+                        authority: new_authority,
+                        durable_nonce,
+                        lamports_per_signature,
+                    }
+                    .write(nonce);
+
+                    Ok(())
+                }
+                _ => Err(ProgramError::InvalidAccountData),
+            }
+        }
+        // Allocate
+        8 => {
+            let space = data.u64()?;
+            let account = index(0)?;
+            let address = *accounts[account].key();
+
+            allocate(&mut accounts[account], &address, space, &signers)
+        }
+        // AllocateWithSeed
+        9 => {
+            let base = data.pubkey()?;
+            let seed = data.string()?;
+            let space = data.u64()?;
+            let owner = data.pubkey()?;
+            let account = &mut accounts[index(0)?];
+            let address = address_with_seed(account.key(), &base, seed, &owner)?;
+
+            allocate(account, &address, space, &signers)?;
+            assign(account, &address, &owner, &signers)
+        }
+        // AssignWithSeed
+        10 => {
+            let base = data.pubkey()?;
+            let seed = data.string()?;
+            let owner = data.pubkey()?;
+            let account = &mut accounts[index(0)?];
+            let address = address_with_seed(account.key(), &base, seed, &owner)?;
+
+            assign(account, &address, &owner, &signers)
+        }
+        // TransferWithSeed
+        11 => {
+            let lamports = data.u64()?;
+            let seed = data.string()?;
+            let owner = data.pubkey()?;
+            let (from, base, to) = (index(0)?, index(1)?, index(2)?);
+
+            if !accounts[base].is_signer() {
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+
+            address_with_seed(accounts[from].key(), accounts[base].key(), seed, &owner)?;
+
+            transfer(accounts, from, to, lamports)
+        }
+        // UpgradeNonceAccount
+        12 => {
+            let nonce = &mut accounts[index(0)?];
+
+            if nonce.owner() != &ID {
+                return Err(ProgramError::InvalidAccountOwner);
+            }
+            if !nonce.is_writable() {
+                return Err(ProgramError::InvalidArgument);
+            }
+
+            match (NonceState::read(nonce)?, nonce.data()[..4] == [0; 4]) {
+                (
+                    NonceState::Initialized {
+                        authority,
+                        durable_nonce,
+                        lamports_per_signature,
+                    },
+                    true,
+                ) => {
+                    NonceState::Initialized {
+                        authority,
+                        durable_nonce: durable_nonce_from(&durable_nonce),
+                        lamports_per_signature,
+                    }
+                    .write(nonce);
+
+                    Ok(())
+                }
+                _ => Err(ProgramError::InvalidArgument),
+            }
+        }
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+/// Allocates space for an account.
+fn allocate(
+    account: &mut TestAccount,
+    address: &Pubkey,
+    space: u64,
+    signers: &[Pubkey],
+) -> ProgramResult {
+    if !signers.contains(address) {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if account.data_len() != 0 || account.owner() != &ID {
+        return Err(SystemError::AccountAlreadyInUse.into());
+    }
+
+    if space > MAX_PERMITTED_DATA_LENGTH {
+        return Err(SystemError::InvalidAccountDataLength.into());
+    }
+
+    account.resize(space as usize);
+
+    Ok(())
+}
+
+/// Assigns an account to a program.
+fn assign(
+    account: &mut TestAccount,
+    address: &Pubkey,
+    owner: &Pubkey,
+    signers: &[Pubkey],
+) -> ProgramResult {
+    if account.owner() == owner {
+        return Ok(());
+    }
+
+    if !signers.contains(address) {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    account.set_owner(owner);
+
+    Ok(())
+}
+
+/// Creates a new account.
+#[allow(clippy::too_many_arguments)]
+fn create_account(
+    accounts: &mut [TestAccount],
+    from: usize,
+    to: usize,
+    address: &Pubkey,
+    lamports: u64,
+    space: u64,
+    owner: &Pubkey,
+    signers: &[Pubkey],
+) -> ProgramResult {
+    if accounts[to].lamports() > 0 {
+        return Err(SystemError::AccountAlreadyInUse.into());
+    }
+
+    allocate(&mut accounts[to], address, space, signers)?;
+    assign(&mut accounts[to], address, owner, signers)?;
+
+    if !accounts[from].is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    transfer(accounts, from, to, lamports)
+}
+
+/// Transfers lamports from an account without data.
+fn transfer(accounts: &mut [TestAccount], from: usize, to: usize, lamports: u64) -> ProgramResult {
+    if accounts[from].data_len() != 0 {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if lamports > accounts[from].lamports() {
+        return Err(SystemError::ResultWithNegativeLamports.into());
+    }
+
+    move_lamports(accounts, from, to, lamports)
+}
+
+/// Moves lamports between accounts.
+fn move_lamports(
+    accounts: &mut [TestAccount],
+    from: usize,
+    to: usize,
+    lamports: u64,
+) -> ProgramResult {
+    let from_lamports = accounts[from]
+        .lamports()
+        .checked_sub(lamports)
+        .ok_or(ProgramError::InsufficientFunds)?;
+    accounts[from].set_lamports(from_lamports);
+
+    let to_lamports = accounts[to]
+        .lamports()
+        .checked_add(lamports)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    accounts[to].set_lamports(to_lamports);
+
+    Ok(())
+}
+
+/// Checks that `address` is derived from `base`, `seed` and `owner`, returning
+/// the base address, which must sign for the account.
+fn address_with_seed(
+    address: &Pubkey,
+    base: &Pubkey,
+    seed: &[u8],
+    owner: &Pubkey,
+) -> Result<Pubkey, ProgramError> {
+    if seed.len() > MAX_SEED_LEN {
+        return Err(SystemError::MaxSeedLengthExceeded.into());
+    }
+
+    if owner.ends_with(PDA_MARKER) {
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    let mut hasher = Sha256::new();
+    hasher.update(base);
+    hasher.update(seed);
+    hasher.update(owner);
+
+    if hasher.finalize() != *address {
+        return Err(SystemError::AddressWithSeedMismatch.into());
+    }
+
+    Ok(*base)
+}
+
+/// Returns the most recent blockhash and its lamports per signature from the
+/// `RecentBlockhashes` sysvar account.
+fn recent_blockhash(account: &TestAccount) -> Result<(Pubkey, u64), ProgramError> {
+    let mut data = Reader(account.data());
+
+    if data.u64().unwrap_or(0) == 0 {
+        return Err(SystemError::NonceNoRecentBlockhashes.into());
+    }
+
+    Ok((data.pubkey()?, data.u64()?))
+}
+
+/// Derives the durable nonce for a blockhash.
+fn durable_nonce_from(blockhash: &Pubkey) -> Pubkey {
+    let mut hasher = Sha256::new();
+    hasher.update(DURABLE_NONCE_HASH_PREFIX);
+    hasher.update(blockhash);
+    hasher.finalize()
+}
+
+/// State of a nonce account.
+enum NonceState {
+    /// Account not initialized.
+    Uninitialized,
+
+    /// Initialized account.
+    Initialized {
+        authority: Pubkey,
+        durable_nonce: Pubkey,
+        lamports_per_signature: u64,
+    },
+}
+
+impl NonceState {
+    /// Reads the state of a nonce account.
+    ///
+    /// Legacy initialized accounts are read as `Initialized`; the version is only
+    /// relevant when upgrading the account.
+    fn read(account: &TestAccount) -> Result<Self, ProgramError> {
+        if account.data_len() != NONCE_STATE_SIZE {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let mut data = Reader(account.data());
+        let _version = data.u32()?;
+
+        match data.u32()? {
+            0 => Ok(NonceState::Uninitialized),
+            1 => Ok(NonceState::Initialized {
+                authority: data.pubkey()?,
+                durable_nonce: data.pubkey()?,
+                lamports_per_signature: data.u64()?,
+            }),
+            _ => Err(ProgramError::InvalidAccountData),
+        }
+    }
+
+    /// Writes the state to a nonce account using the current version.
+    fn write(&self, account: &mut TestAccount) {
+        let data = account.data_mut();
+        data.fill(0);
+        // current version
+        data[..4].copy_from_slice(&1u32.to_le_bytes());
+
+        if let NonceState::Initialized {
+            authority,
+            durable_nonce,
+            lamports_per_signature,
+        } = self
+        {
+            data[4..8].copy_from_slice(&1u32.to_le_bytes());
+            data[8..40].copy_from_slice(authority);
+            data[40..72].copy_from_slice(durable_nonce);
+            data[72..80].copy_from_slice(&lamports_per_signature.to_le_bytes());
+        }
+    }
+}
+
+/// Reads values encoded as the runtime does (`bincode`).
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], ProgramError> {
+        if self.0.len() < len {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let (bytes, remaining) = self.0.split_at(len);
+        self.0 = remaining;
+        Ok(bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32, ProgramError> {
+        Ok(u32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, ProgramError> {
+        Ok(u64::from_le_bytes(self.bytes(8)?.try_into().unwrap()))
+    }
+
+    fn pubkey(&mut self) -> Result<Pubkey, ProgramError> {
+        Ok(self.bytes(32)?.try_into().unwrap())
+    }
+
+    /// Reads a string prefixed by its `u64` length.
+    fn string(&mut self) -> Result<&'a [u8], ProgramError> {
+        let len = self.u64()?;
+        self.bytes(len as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use super::*;
+    use crate::{
+        instruction::{AccountMeta, Instruction},
+        testing::{default_rent, AccountBuilder, Executor, InstructionError, TestAccount},
+    };
+
+    const PAYER: Pubkey = [1; 32];
+    const ACCOUNT: Pubkey = [2; 32];
+    const BASE: Pubkey = [3; 32];
+    const OWNER: Pubkey = [4; 32];
+    const AUTHORITY: Pubkey = [5; 32];
+    const NEW_AUTHORITY: Pubkey = [6; 32];
+    const RECENT_BLOCKHASHES: Pubkey = [7; 32];
+    const RENT: Pubkey = [8; 32];
+
+    /// Encodes the instruction data from its fields.
+    fn encode(fields: &[&[u8]]) -> Vec<u8> {
+        fields.concat()
+    }
+
+    /// Encodes a string prefixed by its `u64` length.
+    fn string(value: &[u8]) -> Vec<u8> {
+        encode(&[&(value.len() as u64).to_le_bytes(), value])
+    }
+
+    fn with_seed(base: &Pubkey, seed: &[u8], owner: &Pubkey) -> Pubkey {
+        let mut hasher = Sha256::new();
+        hasher.update(base);
+        hasher.update(seed);
+        hasher.update(owner);
+        hasher.finalize()
+    }
+
+    fn process(
+        executor: &Executor,
+        metas: &[AccountMeta],
+        data: &[u8],
+        accounts: &mut [TestAccount],
+    ) -> Result<(), InstructionError> {
+        let instruction = Instruction {
+            program_id: &ID,
+            accounts: metas,
+            data,
+        };
+        executor.process_instruction(&instruction, accounts)
+    }
+
+    fn custom(error: SystemError) -> Result<(), InstructionError> {
+        Err(InstructionError::Program(error.into()))
+    }
+
+    #[test]
+    fn test_allocate_assign() {
+        let executor = Executor::new();
+        let mut accounts = [AccountBuilder::new(ACCOUNT).build()];
+        let allocate = encode(&[&8u32.to_le_bytes(), &100u64.to_le_bytes()]);
+
+        assert_eq!(
+            process(
+                &executor,
+                &[AccountMeta::writable(&ACCOUNT)],
+                &allocate,
+                &mut accounts,
+            ),
+            Err(InstructionError::Program(
+                ProgramError::MissingRequiredSignature
+            ))
+        );
+
+        let metas = [AccountMeta::writable_signer(&ACCOUNT)];
+        process(&executor, &metas, &allocate, &mut accounts).unwrap();
+        assert_eq!(accounts[0].data_len(), 100);
+
+        // Allocated accounts cannot be allocated again.
+        assert_eq!(
+            process(&executor, &metas, &allocate, &mut accounts),
+            custom(SystemError::AccountAlreadyInUse)
+        );
+
+        let assign = encode(&[&1u32.to_le_bytes(), &OWNER]);
+        process(&executor, &metas, &assign, &mut accounts).unwrap();
+        assert_eq!(accounts[0].owner(), &OWNER);
+
+        // Assigning the current owner does not require a signature.
+        process(
+            &executor,
+            &[AccountMeta::writable(&ACCOUNT)],
+            &assign,
+            &mut accounts,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_with_seed() {
+        let executor = Executor::new();
+        let created = with_seed(&BASE, b"create", &OWNER);
+        let funded = with_seed(&BASE, b"fund", &ID);
+        let allocated = with_seed(&BASE, b"allocate", &OWNER);
+
+        let mut accounts = [
+            AccountBuilder::new(PAYER).lamports(1_000).build(),
+            AccountBuilder::new(created).build(),
+            AccountBuilder::new(BASE).build(),
+            AccountBuilder::new(funded).lamports(500).build(),
+            AccountBuilder::new(allocated).build(),
+        ];
+
+        // CreateAccountWithSeed
+        let create = |seed: &[u8]| {
+            encode(&[
+                &3u32.to_le_bytes(),
+                &BASE,
+                &string(seed),
+                &100u64.to_le_bytes(),
+                &10u64.to_le_bytes(),
+                &OWNER,
+            ])
+        };
+        let metas = [
+            AccountMeta::writable_signer(&PAYER),
+            AccountMeta::writable(&created),
+            AccountMeta::readonly_signer(&BASE),
+        ];
+
+        assert_eq!(
+            process(&executor, &metas, &create(b"other"), &mut accounts),
+            custom(SystemError::AddressWithSeedMismatch)
+        );
+        assert_eq!(
+            process(&executor, &metas, &create(&[0; 33]), &mut accounts),
+            custom(SystemError::MaxSeedLengthExceeded)
+        );
+
+        process(&executor, &metas, &create(b"create"), &mut accounts).unwrap();
+        assert_eq!(accounts[0].lamports(), 900);
+        assert_eq!(accounts[1].lamports(), 100);
+        assert_eq!(accounts[1].data_len(), 10);
+        assert_eq!(accounts[1].owner(), &OWNER);
+
+        // TransferWithSeed
+        let transfer = encode(&[
+            &11u32.to_le_bytes(),
+            &200u64.to_le_bytes(),
+            &string(b"fund"),
+            &ID,
+        ]);
+        let metas = [
+            AccountMeta::writable(&funded),
+            AccountMeta::readonly(&BASE),
+            AccountMeta::writable(&PAYER),
+        ];
+        assert_eq!(
+            process(&executor, &metas, &transfer, &mut accounts),
+            Err(InstructionError::Program(
+                ProgramError::MissingRequiredSignature
+            ))
+        );
+
+        let metas = [
+            AccountMeta::writable(&funded),
+            AccountMeta::readonly_signer(&BASE),
+            AccountMeta::writable(&PAYER),
+        ];
+        process(&executor, &metas, &transfer, &mut accounts).unwrap();
+        assert_eq!(accounts[3].lamports(), 300);
+        assert_eq!(accounts[0].lamports(), 1_100);
+
+        // AllocateWithSeed
+        let allocate = encode(&[
+            &9u32.to_le_bytes(),
+            &BASE,
+            &string(b"allocate"),
+            &20u64.to_le_bytes(),
+            &OWNER,
+        ]);
+        let metas = [
+            AccountMeta::writable(&allocated),
+            AccountMeta::readonly_signer(&BASE),
+        ];
+        process(&executor, &metas, &allocate, &mut accounts).unwrap();
+        assert_eq!(accounts[4].data_len(), 20);
+        assert_eq!(accounts[4].owner(), &OWNER);
+
+        // AssignWithSeed only checks the address for the new owner.
+        let assign = encode(&[&10u32.to_le_bytes(), &BASE, &string(b"fund"), &OWNER]);
+        let metas = [
+            AccountMeta::writable(&funded),
+            AccountMeta::readonly_signer(&BASE),
+        ];
+        assert_eq!(
+            process(&executor, &metas, &assign, &mut accounts),
+            custom(SystemError::AddressWithSeedMismatch)
+        );
+    }
+
+    #[test]
+    fn test_nonce() {
+        let executor = Executor::new();
+        let minimum_balance = default_rent().minimum_balance(NONCE_STATE_SIZE);
+
+        let recent_blockhashes = |blockhash: &Pubkey| {
+            let data = encode(&[&1u64.to_le_bytes(), blockhash, &5_000u64.to_le_bytes()]);
+            AccountBuilder::new(RECENT_BLOCKHASHES).data(&data).build()
+        };
+
+        let mut accounts = [
+            AccountBuilder::new(ACCOUNT)
+                .lamports(minimum_balance + 1_000)
+                .space(NONCE_STATE_SIZE)
+                .build(),
+            recent_blockhashes(&[1; 32]),
+            AccountBuilder::new(RENT).build(),
+            AccountBuilder::new(PAYER).build(),
+            AccountBuilder::new(AUTHORITY).build(),
+            AccountBuilder::new(NEW_AUTHORITY).build(),
+        ];
+
+        // InitializeNonceAccount
+        let initialize = encode(&[&6u32.to_le_bytes(), &AUTHORITY]);
+        let metas = [
+            AccountMeta::writable(&ACCOUNT),
+            AccountMeta::readonly(&RECENT_BLOCKHASHES),
+            AccountMeta::readonly(&RENT),
+        ];
+        process(&executor, &metas, &initialize, &mut accounts).unwrap();
+
+        let nonce = accounts[0].data();
+        assert_eq!(nonce[4..8], 1u32.to_le_bytes());
+        assert_eq!(nonce[8..40], AUTHORITY);
+        assert_eq!(nonce[40..72], durable_nonce_from(&[1; 32]));
+        assert_eq!(nonce[72..], 5_000u64.to_le_bytes());
+
+        assert_eq!(
+            process(&executor, &metas, &initialize, &mut accounts),
+            Err(InstructionError::Program(ProgramError::InvalidAccountData))
+        );
+
+        // AdvanceNonceAccount
+        let advance = 4u32.to_le_bytes();
+        let metas = [
+            AccountMeta::writable(&ACCOUNT),
+            AccountMeta::readonly(&RECENT_BLOCKHASHES),
+            AccountMeta::readonly_signer(&AUTHORITY),
+        ];
+        assert_eq!(
+            process(&executor, &metas, &advance, &mut accounts),
+            custom(SystemError::NonceBlockhashNotExpired)
+        );
+
+        accounts[1] = recent_blockhashes(&[2; 32]);
+        process(&executor, &metas, &advance, &mut accounts).unwrap();
+        assert_eq!(accounts[0].data()[40..72], durable_nonce_from(&[2; 32]));
+
+        // AuthorizeNonceAccount
+        let authorize = encode(&[&7u32.to_le_bytes(), &NEW_AUTHORITY]);
+        let metas = [
+            AccountMeta::writable(&ACCOUNT),
+            AccountMeta::readonly_signer(&AUTHORITY),
+        ];
+        process(&executor, &metas, &authorize, &mut accounts).unwrap();
+        assert_eq!(accounts[0].data()[8..40], NEW_AUTHORITY);
+
+        // WithdrawNonceAccount, keeping the account rent exempt.
+        let withdraw = |lamports: u64| encode(&[&5u32.to_le_bytes(), &lamports.to_le_bytes()]);
+        let metas = [
+            AccountMeta::writable(&ACCOUNT),
+            AccountMeta::writable(&PAYER),
+            AccountMeta::readonly(&RECENT_BLOCKHASHES),
+            AccountMeta::readonly(&RENT),
+            AccountMeta::readonly_signer(&NEW_AUTHORITY),
+        ];
+        assert_eq!(
+            process(&executor, &metas, &withdraw(1_001), &mut accounts),
+            Err(InstructionError::Program(ProgramError::InsufficientFunds))
+        );
+
+        process(&executor, &metas, &withdraw(1_000), &mut accounts).unwrap();
+        assert_eq!(accounts[0].lamports(), minimum_balance);
+        assert_eq!(accounts[3].lamports(), 1_000);
+
+        // Withdrawing the whole balance deinitializes the account once the
+        // blockhash has changed.
+        assert_eq!(
+            process(&executor, &metas, &withdraw(minimum_balance), &mut accounts),
+            custom(SystemError::NonceBlockhashNotExpired)
+        );
+
+        accounts[1] = recent_blockhashes(&[3; 32]);
+        process(&executor, &metas, &withdraw(minimum_balance), &mut accounts).unwrap();
+        assert_eq!(accounts[0].lamports(), 0);
+        assert_eq!(accounts[0].data()[4..8], 0u32.to_le_bytes());
+    }
+}